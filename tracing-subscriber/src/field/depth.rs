@@ -0,0 +1,173 @@
+//! A reusable depth/recursion bound for hand-written code that walks
+//! attacker- or caller-influenced, recursively-nested values --- e.g. a
+//! [`Visit`](tracing_core::field::Visit) implementation that recurses into a
+//! `valuable`-style structured value, or any other visitor over a tree whose
+//! shape isn't bounded by this crate.
+//!
+//! This is the general shape of the fix for the class of issue behind
+//! RUSTSEC-2023-0078: code that recurses once per level of a caller- or
+//! attacker-controlled nested value can be driven arbitrarily deep and
+//! overflow the stack. [`DepthGuard`] turns that into an explicit, checked
+//! decision at every recursive call site, instead of an unbounded one.
+//!
+//! Note that this only protects recursion that happens in code we write ---
+//! each recursive call threads a `DepthGuard` through via [`DepthGuard::enter`].
+//! It can't bound recursion that happens inside an opaque third-party
+//! `Debug`/`Display` impl, since there's no way to intercept calls made from
+//! inside someone else's code; it's meant for visitors that walk a nested
+//! value's structure themselves.
+
+use std::fmt;
+
+/// How many levels deep a [`DepthGuard`]-threaded recursive walk may go
+/// before it's truncated (conventionally as [`DepthGuard::TRUNCATED`])
+/// rather than recursing further.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepthLimit(usize);
+
+impl DepthLimit {
+    /// A depth generous enough for ordinary nested structs/enums/collections
+    /// in practice, but far short of what could exhaust the stack.
+    pub const DEFAULT: DepthLimit = DepthLimit(32);
+
+    /// Constructs a limit of the given nesting depth.
+    pub fn new(max_depth: usize) -> Self {
+        Self(max_depth)
+    }
+
+    /// Starts a fresh [`DepthGuard`] at depth `0` against this limit.
+    pub fn guard(self) -> DepthGuard {
+        DepthGuard {
+            depth: 0,
+            limit: self.0,
+        }
+    }
+}
+
+impl Default for DepthLimit {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Tracks how deep a recursive walk has gone against a [`DepthLimit`].
+///
+/// Thread this through each recursive call via [`DepthGuard::enter`] rather
+/// than recursing unconditionally; `enter` returns `None` once the limit is
+/// reached, which should be treated as "stop recursing, record
+/// [`DepthGuard::TRUNCATED`] instead".
+#[derive(Clone, Copy, Debug)]
+pub struct DepthGuard {
+    depth: usize,
+    limit: usize,
+}
+
+impl DepthGuard {
+    /// The marker to record in place of further structure once the depth
+    /// limit is reached.
+    pub const TRUNCATED: &'static str = "…(max depth reached)";
+
+    /// Returns a guard for one level deeper than `self`, or `None` if doing
+    /// so would exceed the configured limit.
+    pub fn enter(&self) -> Option<DepthGuard> {
+        if self.depth >= self.limit {
+            None
+        } else {
+            Some(DepthGuard {
+                depth: self.depth + 1,
+                limit: self.limit,
+            })
+        }
+    }
+
+    /// The current recursion depth (`0` at the root).
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+/// Formats `value`'s [`Debug`](fmt::Debug) representation, truncating with
+/// [`DepthGuard::TRUNCATED`] once its bracket/brace/paren nesting crosses
+/// `limit`, instead of recording a potentially unbounded amount of rendered
+/// output for a pathologically deep or wide value.
+///
+/// This is meant for a [`Visit::record_debug`](tracing_core::field::Visit::record_debug)
+/// implementation that stores its fields' rendered `Debug` output (e.g. for
+/// later JSON serialization), as a cap on how much of that output a single
+/// field can contribute.
+///
+/// Note the limitation described in this module's docs: most nested structs,
+/// enums, and collections render their `Debug` output as matched
+/// `{ }`/`[ ]`/`( )` pairs, so capping *that* nesting bounds the output for
+/// those common cases --- but this walks the already-produced output of
+/// `value`'s own `Debug` impl, not the impl's internal recursion, so it
+/// cannot stop a foreign `Debug` impl that recurses arbitrarily deep before
+/// writing anything out from doing so on the call stack. Use [`DepthGuard`]
+/// directly in any visitor that walks caller-controlled nested *structure*
+/// itself, rather than relying on this function, for real recursion-depth
+/// protection.
+pub fn capture_debug(value: &dyn fmt::Debug, limit: DepthLimit) -> String {
+    struct Capped {
+        out: String,
+        // A stack of guards, one per currently-open bracket/brace/paren, so
+        // a closing delimiter can pop back to the depth of its matching
+        // opener instead of nesting only ever deepening. The bottom entry is
+        // always the root-level guard.
+        stack: Vec<DepthGuard>,
+        // Once we've truncated, stay truncated for the rest of the value
+        // rather than resuming when nesting closes back out to an allowed
+        // depth --- a value that's shown itself to be this deep isn't
+        // interesting past that point.
+        truncated: bool,
+    }
+
+    impl fmt::Write for Capped {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            // Once truncated, return `Err` rather than `Ok` from here on.
+            // `DebugStruct`/`DebugTuple`/`DebugList` (and friends) only stop
+            // recursing into further fields when a `write_str` call fails ---
+            // each `.field(...)` call is chained with `and_then`, so an `Err`
+            // short-circuits the remaining fields instead of calling into
+            // their `Debug` impls at all. Returning `Ok` here would let that
+            // native recursion keep descending into the nested value on the
+            // call stack regardless of our depth cap, defeating the point of
+            // truncating in the first place.
+            if self.truncated {
+                return Err(fmt::Error);
+            }
+            for c in s.chars() {
+                match c {
+                    '{' | '[' | '(' => {
+                        let current = *self.stack.last().expect("stack is never empty");
+                        match current.enter() {
+                            Some(deeper) => self.stack.push(deeper),
+                            None => {
+                                self.out.push_str(DepthGuard::TRUNCATED);
+                                self.truncated = true;
+                                return Err(fmt::Error);
+                            }
+                        }
+                    }
+                    '}' | ']' | ')' => {
+                        if self.stack.len() > 1 {
+                            self.stack.pop();
+                        }
+                    }
+                    _ => {}
+                }
+                self.out.push(c);
+            }
+            Ok(())
+        }
+    }
+
+    let mut capped = Capped {
+        out: String::new(),
+        stack: vec![limit.guard()],
+        truncated: false,
+    };
+    if fmt::write(&mut capped, format_args!("{:?}", value)).is_err() && !capped.truncated {
+        capped.out.push_str("<error formatting value>");
+    }
+    capped.out
+}
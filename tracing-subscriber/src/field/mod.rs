@@ -0,0 +1,3 @@
+//! Helpers for recording and formatting span/event field values.
+
+pub mod depth;
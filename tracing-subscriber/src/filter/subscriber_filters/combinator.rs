@@ -0,0 +1,810 @@
+//! Combinators for combining [`Filter`]s.
+//!
+//! [`Filter`]: crate::subscribe::Filter
+use crate::filter::LevelFilter;
+use crate::filter::subscriber_filters::FilterState;
+use crate::subscribe::{Context, Filter};
+use std::{
+    cmp,
+    collections::HashMap,
+    fmt,
+    marker::PhantomData,
+    sync::{atomic::{AtomicUsize, Ordering}, Mutex},
+    time::Instant,
+};
+use tracing_core::{callsite::Identifier, collect::Interest, span, Event, Metadata};
+
+/// A process-wide unique identifier for a single [`Not`] instance.
+///
+/// This is distinct from the `FilterId` of whatever `Filtered` subscriber
+/// hosts the `Not`, since more than one `not_strict()` filter can be
+/// composed under the same `Filtered` (e.g. `a.not_strict().and(b.not_strict())`)
+/// --- keying stashed `enabled` results by the outer `FilterId` alone would
+/// let the second `Not`'s `enabled` call overwrite the first's stashed
+/// result before its matching `event_enabled` call ever reads it back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct NotId(usize);
+
+impl NotId {
+    fn next() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A process-wide unique identifier for a single [`When`] instance, used the
+/// same way [`NotId`] is: to stash, per-event, whether this `When`'s
+/// `predicate` applied during the `enabled` pass, so the `event_enabled` pass
+/// can reuse that decision instead of re-evaluating `predicate.event_enabled`
+/// fresh. The two can disagree --- e.g. a plain `filter_fn` predicate's
+/// `event_enabled` defaults to `true` regardless of what `enabled` decided
+/// --- which would otherwise let `then` run against an event `enabled` never
+/// considered it a match for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct WhenId(usize);
+
+impl WhenId {
+    fn next() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Combines two [`Filter`]s so that spans and events are enabled if and only
+/// if *both* filters enable them.
+///
+/// This type is returned by [`FilterExt::and`]. See that method's
+/// documentation for details.
+///
+/// [`Filter`]: crate::subscribe::Filter
+/// [`FilterExt::and`]: crate::filter::FilterExt::and
+#[derive(Clone)]
+pub struct And<A, B, S> {
+    a: A,
+    b: B,
+    _s: PhantomData<fn(S)>,
+}
+
+/// Combines two [`Filter`]s so that spans and events are enabled if *either*
+/// filter enables them.
+///
+/// This type is returned by [`FilterExt::or`]. See that method's
+/// documentation for details.
+///
+/// [`Filter`]: crate::subscribe::Filter
+/// [`FilterExt::or`]: crate::filter::FilterExt::or
+#[derive(Clone)]
+pub struct Or<A, B, S> {
+    a: A,
+    b: B,
+    _s: PhantomData<fn(S)>,
+}
+
+/// Inverts the result of a [`Filter`].
+///
+/// This type is returned by [`FilterExt::not`] and [`FilterExt::not_strict`].
+/// See those methods' documentation for details.
+///
+/// [`Filter`]: crate::subscribe::Filter
+/// [`FilterExt::not`]: crate::filter::FilterExt::not
+/// [`FilterExt::not_strict`]: crate::filter::FilterExt::not_strict
+#[derive(Clone)]
+pub struct Not<A, S> {
+    a: A,
+    // Set by `FilterExt::not_strict`. See `Filter::enabled` and
+    // `Filter::event_enabled` below for how this changes behavior.
+    strict: bool,
+    // This `Not` instance's own identity, used to key its stashed `enabled`
+    // result so that composing more than one strict `Not` under the same
+    // `Filtered` doesn't have them clobber each other's stash slot. See
+    // `NotId`'s documentation.
+    id: NotId,
+    _s: PhantomData<fn(S)>,
+}
+
+/// Combines two [`Filter`]s so that spans and events are enabled if and only
+/// if *exactly one* of the two filters enables them.
+///
+/// This type is returned by [`FilterExt::xor`]. See that method's
+/// documentation for details.
+///
+/// [`Filter`]: crate::subscribe::Filter
+/// [`FilterExt::xor`]: crate::filter::FilterExt::xor
+#[derive(Clone)]
+pub struct Xor<A, B, S> {
+    a: A,
+    b: B,
+    _s: PhantomData<fn(S)>,
+}
+
+/// Applies a `then` [`Filter`] only to spans/events matched by a `predicate`
+/// filter, and otherwise falls through to enabling them unconditionally.
+///
+/// This type is returned by [`FilterExt::when`]. See that method's
+/// documentation for details.
+///
+/// [`Filter`]: crate::subscribe::Filter
+/// [`FilterExt::when`]: crate::filter::FilterExt::when
+#[derive(Clone)]
+pub struct When<P, T, S> {
+    predicate: P,
+    then: T,
+    // This `When` instance's own identity, used to key whether `predicate`
+    // applied to a given event, as decided by `enabled`, so `event_enabled`
+    // can reuse that decision. See `WhenId`'s documentation.
+    id: WhenId,
+    _s: PhantomData<fn(S)>,
+}
+
+// === impl And ===
+
+impl<A, B, S> And<A, B, S> {
+    pub(crate) fn new(a: A, b: B) -> Self {
+        Self {
+            a,
+            b,
+            _s: PhantomData,
+        }
+    }
+}
+
+impl<A, B, S> Filter<S> for And<A, B, S>
+where
+    A: Filter<S>,
+    B: Filter<S>,
+{
+    fn enabled(&self, meta: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        self.a.enabled(meta, cx) && self.b.enabled(meta, cx)
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        let a = self.a.callsite_enabled(meta);
+        let b = self.b.callsite_enabled(meta);
+
+        // If either filter disables the callsite, the combined filter
+        // disables it, full stop --- we don't need to ask the other filter
+        // or re-evaluate on every span/event.
+        if a.is_never() || b.is_never() {
+            return Interest::never();
+        }
+
+        // Otherwise, if *both* filters unconditionally enable the callsite,
+        // so does the combined filter. If only one of the two does, or
+        // neither does, we have to ask again for each span/event, since that
+        // might change the outcome.
+        if a.is_always() && b.is_always() {
+            Interest::always()
+        } else {
+            Interest::sometimes()
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        // Since both filters must enable a span/event for it to be enabled,
+        // the combined maximum level is the *lower* of the two filters'
+        // hints.
+        cmp::min(self.a.max_level_hint(), self.b.max_level_hint())
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, cx: &Context<'_, S>) -> bool {
+        self.a.event_enabled(event, cx) && self.b.event_enabled(event, cx)
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_new_span(attrs, id, ctx.clone());
+        self.b.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        self.a.on_record(id, values, ctx.clone());
+        self.b.on_record(id, values, ctx);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_enter(id, ctx.clone());
+        self.b.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_exit(id, ctx.clone());
+        self.b.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.a.on_close(id.clone(), ctx.clone());
+        self.b.on_close(id, ctx);
+    }
+}
+
+impl<A, B, S> fmt::Debug for And<A, B, S>
+where
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("And")
+            .field("a", &self.a)
+            .field("b", &self.b)
+            .finish()
+    }
+}
+
+// === impl Or ===
+
+impl<A, B, S> Or<A, B, S> {
+    pub(crate) fn new(a: A, b: B) -> Self {
+        Self {
+            a,
+            b,
+            _s: PhantomData,
+        }
+    }
+}
+
+impl<A, B, S> Filter<S> for Or<A, B, S>
+where
+    A: Filter<S>,
+    B: Filter<S>,
+{
+    fn enabled(&self, meta: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        self.a.enabled(meta, cx) || self.b.enabled(meta, cx)
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        let a = self.a.callsite_enabled(meta);
+        let b = self.b.callsite_enabled(meta);
+
+        // If either filter unconditionally enables the callsite, so does the
+        // combined filter.
+        if a.is_always() || b.is_always() {
+            return Interest::always();
+        }
+
+        // If *both* filters disable the callsite, so does the combined
+        // filter. Otherwise, we have to ask again for each span/event.
+        if a.is_never() && b.is_never() {
+            Interest::never()
+        } else {
+            Interest::sometimes()
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        // Since a span/event is enabled if *either* filter enables it, the
+        // combined maximum level is the *higher* of the two filters' hints
+        // --- and only if both filters provide a hint at all.
+        match (self.a.max_level_hint(), self.b.max_level_hint()) {
+            (Some(a), Some(b)) => Some(cmp::max(a, b)),
+            _ => None,
+        }
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, cx: &Context<'_, S>) -> bool {
+        self.a.event_enabled(event, cx) || self.b.event_enabled(event, cx)
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_new_span(attrs, id, ctx.clone());
+        self.b.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        self.a.on_record(id, values, ctx.clone());
+        self.b.on_record(id, values, ctx);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_enter(id, ctx.clone());
+        self.b.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_exit(id, ctx.clone());
+        self.b.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.a.on_close(id.clone(), ctx.clone());
+        self.b.on_close(id, ctx);
+    }
+}
+
+impl<A, B, S> fmt::Debug for Or<A, B, S>
+where
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Or")
+            .field("a", &self.a)
+            .field("b", &self.b)
+            .finish()
+    }
+}
+
+// === impl Not ===
+
+impl<A, S> Not<A, S> {
+    pub(crate) fn new(a: A) -> Self {
+        Self {
+            a,
+            strict: false,
+            id: NotId::next(),
+            _s: PhantomData,
+        }
+    }
+
+    /// Like `new`, but also inverts `event_enabled`, at the cost of stashing
+    /// the wrapped filter's `enabled` result on the `FILTERING` thread-local
+    /// so it can be folded in when `event_enabled` runs. See
+    /// `FilterExt::not_strict`'s documentation.
+    pub(crate) fn new_strict(a: A) -> Self {
+        Self {
+            a,
+            strict: true,
+            id: NotId::next(),
+            _s: PhantomData,
+        }
+    }
+}
+
+impl<A, S> Filter<S> for Not<A, S>
+where
+    A: Filter<S>,
+{
+    fn enabled(&self, meta: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        let a_enabled = self.a.enabled(meta, cx);
+
+        // In strict mode, for events (which get a subsequent `event_enabled`
+        // pass), we can't finalize the decision here: if we returned
+        // `!a_enabled` and it was `false`, `Filtered::event_enabled` would
+        // short-circuit and never call `event_enabled` below, so a case
+        // where `a.enabled()` is `true` but `a.event_enabled()` would have
+        // rejected the event could never be inverted. Instead, stash
+        // `a_enabled` and optimistically report `true`, deferring to
+        // `event_enabled` to compute the real `!(enabled() &&
+        // event_enabled())`.
+        if self.strict && meta.is_event() {
+            if cx.filter_id().is_some() {
+                FilterState::stash_not_result(self.id, a_enabled);
+            }
+            return true;
+        }
+
+        !a_enabled
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        match self.a.callsite_enabled(meta) {
+            interest if interest.is_always() => Interest::never(),
+            // In strict mode, `enabled` has to run for every event so it can
+            // stash the wrapped filter's result for `event_enabled`; an
+            // `always` interest would skip `enabled` entirely.
+            interest if interest.is_never() && self.strict => Interest::sometimes(),
+            interest if interest.is_never() => Interest::always(),
+            _ => Interest::sometimes(),
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        // Inverting a filter could enable *any* level, depending on what
+        // exactly the wrapped filter disables, so we can't provide a
+        // meaningful hint here.
+        None
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, cx: &Context<'_, S>) -> bool {
+        // See `FilterExt::not`'s documentation for why this isn't inverted
+        // by default.
+        if !self.strict {
+            return true;
+        }
+
+        let a_enabled = if cx.filter_id().is_some() {
+            FilterState::take_not_result(self.id).unwrap_or(true)
+        } else {
+            true
+        };
+        !(a_enabled && self.a.event_enabled(event, cx))
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        self.a.on_record(id, values, ctx);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.a.on_close(id, ctx);
+    }
+}
+
+impl<A, S> fmt::Debug for Not<A, S>
+where
+    A: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Not")
+            .field("a", &self.a)
+            .field("strict", &self.strict)
+            .finish()
+    }
+}
+
+// === impl Xor ===
+
+impl<A, B, S> Xor<A, B, S> {
+    pub(crate) fn new(a: A, b: B) -> Self {
+        Self {
+            a,
+            b,
+            _s: PhantomData,
+        }
+    }
+}
+
+impl<A, B, S> Filter<S> for Xor<A, B, S>
+where
+    A: Filter<S>,
+    B: Filter<S>,
+{
+    fn enabled(&self, meta: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        self.a.enabled(meta, cx) ^ self.b.enabled(meta, cx)
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        let a = self.a.callsite_enabled(meta);
+        let b = self.b.callsite_enabled(meta);
+
+        // Unlike `And` and `Or`, `Xor` can't short-circuit on just one of
+        // the two filters --- the outcome always depends on *both* sides.
+        // We can only settle on a static `Interest` when both sides are
+        // themselves static (`always` or `never`); if either one is
+        // `sometimes`, we have to re-evaluate per span/event.
+        match (a.is_always(), a.is_never(), b.is_always(), b.is_never()) {
+            (true, _, true, _) | (_, true, _, true) => Interest::never(),
+            (true, _, _, true) | (_, true, true, _) => Interest::always(),
+            _ => Interest::sometimes(),
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        // There's no meaningful single "maximum level" for an exclusive-or
+        // of two filters: whether a given level is enabled depends on
+        // whether the *other* side also enables it.
+        None
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, cx: &Context<'_, S>) -> bool {
+        self.a.event_enabled(event, cx) ^ self.b.event_enabled(event, cx)
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_new_span(attrs, id, ctx.clone());
+        self.b.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        self.a.on_record(id, values, ctx.clone());
+        self.b.on_record(id, values, ctx);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_enter(id, ctx.clone());
+        self.b.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.a.on_exit(id, ctx.clone());
+        self.b.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.a.on_close(id.clone(), ctx.clone());
+        self.b.on_close(id, ctx);
+    }
+}
+
+impl<A, B, S> fmt::Debug for Xor<A, B, S>
+where
+    A: fmt::Debug,
+    B: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Xor")
+            .field("a", &self.a)
+            .field("b", &self.b)
+            .finish()
+    }
+}
+
+// === impl When ===
+
+impl<P, T, S> When<P, T, S> {
+    pub(crate) fn new(predicate: P, then: T) -> Self {
+        Self {
+            predicate,
+            then,
+            id: WhenId::next(),
+            _s: PhantomData,
+        }
+    }
+}
+
+impl<P, T, S> Filter<S> for When<P, T, S>
+where
+    P: Filter<S>,
+    T: Filter<S>,
+{
+    fn enabled(&self, meta: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        let applies = self.predicate.enabled(meta, cx);
+
+        // Stash whether the predicate applied, so the `event_enabled` pass
+        // below can reuse this decision rather than asking
+        // `predicate.event_enabled` fresh and potentially getting a
+        // different answer.
+        if meta.is_event() && cx.filter_id().is_some() {
+            FilterState::stash_when_applies(self.id, applies);
+        }
+
+        if applies {
+            self.then.enabled(meta, cx)
+        } else {
+            // The predicate didn't match, so we fall through to the
+            // default of enabling the span/event, and leave the decision up
+            // to whatever filter is combined with this one.
+            true
+        }
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        if self.predicate.callsite_enabled(meta).is_never() {
+            // The predicate will never match this callsite, so we always
+            // fall through to enabling it.
+            return Interest::always();
+        }
+
+        // Otherwise, whether we enable this callsite depends on whether the
+        // predicate matches *this* span/event, which can only be known once
+        // we see it.
+        Interest::sometimes()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        // Since spans/events that don't match the predicate fall through to
+        // being enabled, this filter can't provide a useful maximum level
+        // hint in general.
+        None
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, cx: &Context<'_, S>) -> bool {
+        // Reuse whatever `enabled` already decided about whether the
+        // predicate applies to this event, rather than asking
+        // `predicate.event_enabled` fresh --- the two can disagree (e.g. a
+        // plain `filter_fn` predicate's `event_enabled` defaults to `true`
+        // regardless of what `enabled` returned), which would otherwise run
+        // `then` against an event `enabled` never considered a match. If
+        // nothing was stashed (`enabled` was skipped because the callsite's
+        // interest was `always`, or we're not in a filtering context that
+        // stashes this), ask the predicate's `enabled` directly.
+        let applies = if cx.filter_id().is_some() {
+            FilterState::take_when_applies(self.id)
+        } else {
+            None
+        }
+        .unwrap_or_else(|| self.predicate.enabled(event.metadata(), cx));
+
+        if applies {
+            self.then.event_enabled(event, cx)
+        } else {
+            true
+        }
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        self.predicate.on_new_span(attrs, id, ctx.clone());
+        self.then.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        self.predicate.on_record(id, values, ctx.clone());
+        self.then.on_record(id, values, ctx);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.predicate.on_enter(id, ctx.clone());
+        self.then.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.predicate.on_exit(id, ctx.clone());
+        self.then.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.predicate.on_close(id.clone(), ctx.clone());
+        self.then.on_close(id, ctx);
+    }
+}
+
+impl<P, T, S> fmt::Debug for When<P, T, S>
+where
+    P: fmt::Debug,
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("When")
+            .field("predicate", &self.predicate)
+            .field("then", &self.then)
+            .finish()
+    }
+}
+
+// === impl Sampling ===
+
+/// A [`Filter`] that downsamples spans and events on a per-callsite basis,
+/// rather than disabling a callsite entirely.
+///
+/// This is constructed by [`Sampling::every_nth`] or [`Sampling::per_second`],
+/// and is typically combined with another [`Filter`] using
+/// [`FilterExt::sample_rate`], so that high-volume callsites (e.g. a `DEBUG`
+/// span in a hot loop) can be thinned out without losing visibility into the
+/// callsite entirely.
+///
+/// Each callsite this filter is consulted for gets its own independent
+/// sampling state, keyed by the callsite's [`Identifier`], so sampling one
+/// callsite does not affect the sampling decisions made for any other.
+///
+/// Because the sampling decision depends on mutable per-callsite state that
+/// changes on every call, [`Filter::callsite_enabled`] always returns
+/// [`Interest::sometimes`] for a `Sampling` filter — it can never answer
+/// `always` or `never` once and be done with it.
+///
+/// [`Filter`]: crate::subscribe::Filter
+/// [`FilterExt::sample_rate`]: crate::filter::FilterExt::sample_rate
+pub struct Sampling<S> {
+    mode: SamplingMode,
+    state: Mutex<HashMap<Identifier, SamplingState>>,
+    _s: PhantomData<fn(S)>,
+}
+
+enum SamplingMode {
+    /// Deterministically enables 1 out of every `n` calls.
+    EveryNth(u64),
+    /// Enables up to `rate` calls per second, with bursts of up to `burst`
+    /// calls permitted at once.
+    PerSecond { rate: f64, burst: f64 },
+}
+
+enum SamplingState {
+    Counter(u64),
+    TokenBucket { tokens: f64, last_refill: Instant },
+}
+
+impl<S> Sampling<S> {
+    /// Returns a `Sampling` filter that deterministically enables exactly 1
+    /// out of every `n` spans/events at each callsite it's applied to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is `0`.
+    pub fn every_nth(n: u64) -> Self {
+        assert!(n > 0, "sampling rate must be greater than 0");
+        Self {
+            mode: SamplingMode::EveryNth(n),
+            state: Mutex::new(HashMap::new()),
+            _s: PhantomData,
+        }
+    }
+
+    /// Returns a `Sampling` filter that enables up to `events_per_sec`
+    /// spans/events per second at each callsite, using a token-bucket with
+    /// room for `burst` calls to be enabled at once even if the rate has not
+    /// been refilled yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `events_per_sec` is not a positive number.
+    pub fn per_second(events_per_sec: f64, burst: f64) -> Self {
+        assert!(
+            events_per_sec > 0.0,
+            "sampling rate must be a positive number of events per second"
+        );
+        Self {
+            mode: SamplingMode::PerSecond {
+                rate: events_per_sec,
+                burst: burst.max(1.0),
+            },
+            state: Mutex::new(HashMap::new()),
+            _s: PhantomData,
+        }
+    }
+
+    /// Consults (and updates) the sampling state for `callsite`, returning
+    /// whether this call should be enabled.
+    fn sample(&self, callsite: Identifier) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match &self.mode {
+            SamplingMode::EveryNth(n) => {
+                let entry = state.entry(callsite).or_insert(SamplingState::Counter(0));
+                let SamplingState::Counter(count) = entry else {
+                    unreachable!("a callsite's sampling state kind never changes")
+                };
+                let enabled = *count % n == 0;
+                *count = count.wrapping_add(1);
+                enabled
+            }
+            SamplingMode::PerSecond { rate, burst } => {
+                let now = Instant::now();
+                let entry = state.entry(callsite).or_insert(SamplingState::TokenBucket {
+                    tokens: *burst,
+                    last_refill: now,
+                });
+                let SamplingState::TokenBucket { tokens, last_refill } = entry else {
+                    unreachable!("a callsite's sampling state kind never changes")
+                };
+                let elapsed = now.saturating_duration_since(*last_refill).as_secs_f64();
+                *tokens = (*tokens + elapsed * rate).min(*burst);
+                *last_refill = now;
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+impl<S> Filter<S> for Sampling<S> {
+    fn enabled(&self, meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        self.sample(meta.callsite())
+    }
+
+    fn event_enabled(&self, _event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        // The sampling decision was already made (and the per-callsite
+        // counter/token already consumed) in `enabled` above, which always
+        // runs first. If we sampled again here, a combinator like `And`
+        // would call both methods for every event and consume the counter
+        // twice per event instead of once, so `event_enabled` just defers to
+        // that already-settled decision.
+        true
+    }
+
+    fn callsite_enabled(&self, _meta: &'static Metadata<'static>) -> Interest {
+        // The sampling decision is re-evaluated on every call and depends on
+        // mutable state, so we can never tell the collector `always` or
+        // `never` up front.
+        Interest::sometimes()
+    }
+}
+
+impl<S> fmt::Debug for Sampling<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sampling").field("mode", &self.mode).finish()
+    }
+}
+
+impl fmt::Debug for SamplingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EveryNth(n) => f.debug_tuple("EveryNth").field(n).finish(),
+            Self::PerSecond { rate, burst } => f
+                .debug_struct("PerSecond")
+                .field("rate", rate)
+                .field("burst", burst)
+                .finish(),
+        }
+    }
+}
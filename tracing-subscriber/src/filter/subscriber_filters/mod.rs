@@ -39,7 +39,10 @@ use std::{
     marker::PhantomData,
     ops::Deref,
     ptr::NonNull,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     thread_local,
 };
 use tracing_core::{
@@ -73,13 +76,21 @@ pub struct Filtered<S, F, C> {
 /// will then use the generated ID to query whether a particular span was
 /// previously enabled by that subscriber's [`Filter`].
 ///
-/// **Note**: Currently, the [`Registry`] type provided by this crate is the
-/// **only** [`Collect`][collector] implementation capable of participating in per-subscriber
-/// filtering. Therefore, the `FilterId` type cannot currently be constructed by
-/// code outside of `tracing-subscriber`. In the future, new APIs will be added to `tracing-subscriber` to
-/// allow non-Registry [collector]s to also participate in per-subscriber
-/// filtering. When those APIs are added, subscribers will be responsible
-/// for generating and assigning `FilterId`s.
+/// The [`Registry`] type provided by this crate generates `FilterId`s out of
+/// the box. Any other [`Collect`][collector] implementation (for instance, a
+/// bounded or ring-buffer-backed span store) that wants to host its own
+/// per-subscriber [`Filtered`] subscribers can do the same by overriding
+/// [`Collect::register_filter`][register_filter], the existing extension
+/// point this crate's [`Filtered::on_subscribe`] already calls into;
+/// [`FilterIdAllocator`] provides a reusable counter for doing so without
+/// reimplementing `FilterId` bookkeeping.
+///
+/// [register_filter]: tracing_core::Collect::register_filter
+///
+/// `FilterId`s are backed by a [`Bitset`], so a collector may generate more
+/// than 64 of them; see that type's documentation for details on how it
+/// stays allocation-free for the common case of 64 or fewer per-subscriber
+/// filters.
 ///
 /// [`Filter`]: crate::subscribe::Filter
 /// [collector]: tracing_core::Collect
@@ -87,26 +98,294 @@ pub struct Filtered<S, F, C> {
 /// [`Registry`]: crate::registry::Registry
 #[cfg(feature = "registry")]
 #[cfg_attr(docsrs, doc(cfg(feature = "registry")))]
-#[derive(Copy, Clone)]
-pub struct FilterId(u64);
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct FilterId {
+    bits: Bitset,
+    disabled: bool,
+}
 
 /// A bitmap tracking which [`FilterId`]s have enabled a given span or
 /// event.
 ///
 /// This is currently a private type that's used exclusively by the
-/// [`Registry`]. However, in the future, this may become a public API, in order
-/// to allow user subscribers to host [`Filter`]s.
+/// [`Registry`]. A read-only view over it is exposed publicly as
+/// [`FilterOutcome`], for subscribers and diagnostic tools that want to know
+/// which per-subscriber filters disabled the current span/event.
 ///
 /// [`Registry`]: crate::Registry
 /// [`Filter`]: crate::subscribe::Filter
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq)]
 pub(crate) struct FilterMap {
-    bits: u64,
+    bits: Bitset,
+}
+
+/// A read-only snapshot of which per-subscriber [`Filter`]s have disabled
+/// (or enabled) the span or event currently being processed.
+///
+/// This lets a subscriber or diagnostic tool ask, from within an `enabled`
+/// or `event_enabled` call, which [`Filtered`] subscribers elsewhere in the
+/// stack rejected the current metadata, and which let it through, without
+/// resorting to the `MagicPsfDowncastMarker` downcast hack.
+///
+/// Each [`Filtered`] subscriber's stable handle is the [`FilterId`] returned
+/// by its collector's [`Collect::register_filter`][register_filter] call
+/// (for a [`Registry`], the `FilterId` it hands out internally); compare
+/// that handle against a `FilterOutcome` with [`is_enabled_by`] to find out
+/// whether that particular filter disabled the current span/event.
+///
+/// [register_filter]: tracing_core::Collect::register_filter
+///
+/// [`Filter`]: crate::subscribe::Filter
+/// [`Filtered`]: crate::filter::subscriber_filters::Filtered
+/// [`is_enabled_by`]: FilterOutcome::is_enabled_by
+///
+/// # Storing a per-span outcome
+///
+/// [`FilterOutcome::current`] only reflects the filtering pass currently in
+/// progress; it doesn't persist anything on its own. A collector that wants
+/// to ask the same question about a span *after* that span's own filtering
+/// pass has finished --- say, when walking a closed span's ancestry later ---
+/// needs to snapshot and store a `FilterOutcome` itself, the same way this
+/// crate's own [`Registry`] stores one in each span's extensions when it's
+/// created. Since `FilterOutcome` is a plain `Clone`, `Debug` value, a
+/// non-`Registry` collector (e.g. a bounded or ring-buffer-backed span
+/// store) can do the same with its own span storage:
+///
+/// ```ignore
+/// fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, _cx: Context<'_, C>) {
+///     // Snapshot which filters have (so far) disabled this span, and stash
+///     // it alongside whatever else this collector already records per span.
+///     self.spans.insert(id.clone(), FilterOutcome::current());
+/// }
+///
+/// // ...later, e.g. while building an event's ancestry:
+/// if let Some(outcome) = self.spans.get(&ancestor_id) {
+///     if !outcome.is_enabled_by(&some_filter_id) {
+///         // `some_filter_id` had disabled this ancestor span.
+///     }
+/// }
+/// ```
+///
+/// [`Registry`]: crate::registry::Registry
+#[cfg(feature = "registry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "registry")))]
+#[derive(Clone, Debug)]
+pub struct FilterOutcome {
+    map: FilterMap,
+}
+
+#[cfg(feature = "registry")]
+impl Default for FilterOutcome {
+    /// Returns an outcome in which no [`Filtered`] subscriber has yet
+    /// disabled the span or event --- useful for initializing a collector's
+    /// own per-span storage before any filtering pass has run for that span.
+    ///
+    /// [`Filtered`]: crate::filter::subscriber_filters::Filtered
+    fn default() -> Self {
+        Self {
+            map: FilterMap::new(),
+        }
+    }
 }
 
 impl FilterMap {
     pub(crate) const fn new() -> Self {
-        Self { bits: 0 }
+        Self {
+            bits: Bitset::new(),
+        }
+    }
+}
+
+/// A reusable, monotonically increasing [`FilterId`] counter.
+///
+/// [`Filtered`] subscribers need a [`FilterId`] to record, in thread-local
+/// [`FilterState`], whether their [`Filter`] disabled a given span or event;
+/// a collector hands one out from its
+/// [`Collect::register_filter`][register_filter] implementation (the
+/// [`Registry`] provided by this crate does so internally). A custom
+/// [collector] that wants to host per-subscriber [`Filtered`] subscribers of
+/// its own --- for example, a bounded or ring-buffer-backed span store ---
+/// can override `register_filter` directly; most implementations will only
+/// need a monotonically increasing counter to hand out distinct `FilterId`s,
+/// which is exactly what `FilterIdAllocator` provides, so it can be embedded
+/// in such a collector and delegated to from `register_filter` rather than
+/// reimplementing `FilterId` bookkeeping from scratch.
+///
+/// [`Filter`]: crate::subscribe::Filter
+/// [collector]: tracing_core::Collect
+/// [register_filter]: tracing_core::Collect::register_filter
+/// [`Registry`]: crate::registry::Registry
+///
+/// ```
+/// use tracing_subscriber::filter::FilterIdAllocator;
+///
+/// struct MyCollector {
+///     filters: FilterIdAllocator,
+///     // ...other fields omitted...
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct FilterIdAllocator(AtomicUsize);
+
+impl FilterIdAllocator {
+    /// Returns a new, empty `FilterIdAllocator`.
+    pub const fn new() -> Self {
+        Self(AtomicUsize::new(0))
+    }
+
+    /// Allocates and returns the next `FilterId`.
+    pub fn next(&self) -> FilterId {
+        FilterId::new(self.0.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A growable bitset used to back both [`FilterId`] and [`FilterMap`].
+///
+/// Per-subscriber filtering was originally capped at 64 [`Filtered`]
+/// subscribers per collector, because the bitmap tracking which filters had
+/// disabled a span or event was a single `u64`. Large, plugin-heavy
+/// applications (one `Filtered` subscriber per tenant or per output sink,
+/// say) routinely exceed that limit. `Bitset` keeps the first 64 bits
+/// inline (so the overwhelmingly common case of ≤64 filters never
+/// allocates), and spills onto the heap as a boxed slice of `u64` words only
+/// once a 65th filter is registered.
+///
+/// The hot-path operations (`is_set`, `set`, `any`) stay branch-light: the
+/// inline case is just a `u64` load/store, and the spilled case is a slice
+/// index. Only `grow_for` (called by every `set` and `or_with`, and
+/// allocating whenever a bit past the current width is set, e.g. each time a
+/// filter past bit 64 marks a freshly reset inline map) and the `or`-based
+/// combination in [`FilterId::and`] need to reason about variable width.
+#[derive(Clone)]
+enum Bitset {
+    Inline(u64),
+    Spilled(Box<[u64]>),
+}
+
+impl Bitset {
+    const BITS_PER_WORD: usize = u64::BITS as usize;
+
+    const fn new() -> Self {
+        Self::Inline(0)
+    }
+
+    /// Returns a `Bitset` with only the given `word`/`bit` set.
+    fn single(word: usize, bit: u32) -> Self {
+        let mut this = Self::new();
+        this.set(word, bit);
+        this
+    }
+
+    fn words(&self) -> &[u64] {
+        match self {
+            Self::Inline(word) => std::slice::from_ref(word),
+            Self::Spilled(words) => words,
+        }
+    }
+
+    fn word(&self, word: usize) -> u64 {
+        self.words().get(word).copied().unwrap_or(0)
+    }
+
+    /// Grows the backing storage, if necessary, so that `word` is a valid
+    /// index. This is the only operation that may allocate.
+    fn grow_for(&mut self, word: usize) {
+        if word == 0 {
+            return;
+        }
+        match self {
+            Self::Inline(bits) => {
+                let mut words = vec![0u64; word + 1];
+                words[0] = *bits;
+                *self = Self::Spilled(words.into_boxed_slice());
+            }
+            Self::Spilled(words) if words.len() <= word => {
+                let mut new_words = vec![0u64; word + 1];
+                new_words[..words.len()].copy_from_slice(words);
+                *words = new_words.into_boxed_slice();
+            }
+            Self::Spilled(_) => {}
+        }
+    }
+
+    fn set(&mut self, word: usize, bit: u32) {
+        self.grow_for(word);
+        match self {
+            Self::Inline(bits) => *bits |= 1 << bit,
+            Self::Spilled(words) => words[word] |= 1 << bit,
+        }
+    }
+
+    fn is_set(&self, word: usize, bit: u32) -> bool {
+        self.word(word) & (1 << bit) != 0
+    }
+
+    /// Returns `true` if `self` and `other` have any bit in common.
+    fn intersects(&self, other: &Self) -> bool {
+        let len = self.words().len().max(other.words().len());
+        (0..len).any(|i| self.word(i) & other.word(i) != 0)
+    }
+
+    /// OR's `other`'s bits into `self`, growing `self` if necessary.
+    fn or_with(&mut self, other: &Self) {
+        let other_len = other.words().len();
+        if other_len > 0 {
+            self.grow_for(other_len - 1);
+        }
+        match self {
+            Self::Inline(bits) => *bits |= other.word(0),
+            Self::Spilled(words) => {
+                for (i, word) in words.iter_mut().enumerate() {
+                    *word |= other.word(i);
+                }
+            }
+        }
+    }
+
+    /// Clears every bit in `self` that's set in `other`, without growing.
+    fn and_not(&mut self, other: &Self) {
+        match self {
+            Self::Inline(bits) => *bits &= !other.word(0),
+            Self::Spilled(words) => {
+                for (i, word) in words.iter_mut().enumerate() {
+                    *word &= !other.word(i);
+                }
+            }
+        }
+    }
+
+    fn any(&self) -> bool {
+        self.words().iter().any(|&w| w != 0)
+    }
+
+    /// Mirrors the historical `u64::MAX` sentinel: `true` if every word that
+    /// has been allocated is entirely set.
+    fn is_all_ones(&self) -> bool {
+        self.any() && self.words().iter().all(|&w| w == u64::MAX)
+    }
+}
+
+impl PartialEq for Bitset {
+    fn eq(&self, other: &Self) -> bool {
+        let len = self.words().len().max(other.words().len());
+        (0..len).all(|i| self.word(i) == other.word(i))
+    }
+}
+
+impl Eq for Bitset {}
+
+impl std::hash::Hash for Bitset {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        // Hash only the "logical" words (i.e. trimming trailing all-zero
+        // words), so that two `Bitset`s comparing equal under `PartialEq`
+        // (which also ignores trailing zero words) also hash equally.
+        let words = self.words();
+        let len = words
+            .iter()
+            .rposition(|&w| w != 0)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        words[..len].hash(state);
     }
 }
 
@@ -138,13 +417,40 @@ impl FilterMap {
 ///     recording a span or event can be skipped entirely.
 #[derive(Debug)]
 pub(crate) struct FilterState {
-    enabled: Cell<FilterMap>,
+    // Now that `FilterMap` may spill onto the heap past 64 registered
+    // filters, it's no longer `Copy`, so this can't be a `Cell` the way it
+    // used to be.
+    enabled: RefCell<FilterMap>,
     // TODO(eliza): `Interest`s should _probably_ be `Copy`. The only reason
     // they're not is our Obsessive Commitment to Forwards-Compatibility. If
     // this changes in tracing-core`, we can make this a `Cell` rather than
     // `RefCell`...
     interest: RefCell<Option<Interest>>,
 
+    // Records the `enabled` outcome of a `combinator::Not` constructed via
+    // `FilterExt::not_strict`, keyed by that `Not` instance's own `NotId`
+    // (not by the `FilterId` of the `Filtered` subscriber hosting it, since
+    // more than one strict `Not` can be composed under the same `Filtered`).
+    // This lets `Not::event_enabled` compute a true `!(enabled() &&
+    // event_enabled())`, rather than the cheap (but sometimes wrong) default
+    // of leaving `event_enabled` un-inverted. See `FilterExt::not`'s
+    // documentation for why this bookkeeping is normally skipped.
+    //
+    // This is a `Vec` rather than a `HashMap`, since in practice very few
+    // `Filtered` subscribers (if any) will opt into strict `not` filtering on
+    // a given thread, so a linear scan is cheaper than hashing --- and,
+    // unlike `HashMap::new`, `Vec::new` is a `const fn`, which we need here.
+    not_state: RefCell<Vec<(combinator::NotId, bool)>>,
+
+    // Records whether a `combinator::When`'s `predicate` applied to the
+    // current event, as decided by its `enabled` call, keyed by that
+    // `When` instance's own `WhenId`. This lets `When::event_enabled` reuse
+    // that decision instead of asking `predicate.event_enabled` fresh, which
+    // can disagree with `predicate.enabled` (e.g. for a plain `filter_fn`
+    // predicate, whose `event_enabled` defaults to `true`). See `not_state`
+    // above for why this is a `Vec` rather than a `HashMap`.
+    when_state: RefCell<Vec<(combinator::WhenId, bool)>>,
+
     #[cfg(debug_assertions)]
     counters: DebugCounters,
 }
@@ -378,6 +684,142 @@ pub trait FilterExt<S>: subscribe::Filter<S> {
         combinator::Not::new(self)
     }
 
+    /// Like [`not`], but correctly inverts [`event_enabled`] as well, at the
+    /// cost of the additional thread-local bookkeeping described in [`not`]'s
+    /// documentation.
+    ///
+    /// Use this when wrapping a filter whose [`event_enabled`] actually
+    /// disables events based on field values (rather than returning the
+    /// default `true`), and the inversion needs to be exact rather than
+    /// approximate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tracing_subscriber::filter::{filter_fn, FilterExt};
+    ///
+    /// let target_filter = filter_fn(|meta| meta.target().starts_with("interesting_target"));
+    ///
+    /// // Enabled only for spans and events *without* the `interesting_target`
+    /// // target, with an exact (rather than approximate) inversion.
+    /// let filter = target_filter.not_strict();
+    /// ```
+    ///
+    /// [`not`]: FilterExt::not
+    /// [`event_enabled`]: crate::subscribe::Filter::event_enabled
+    fn not_strict(self) -> combinator::Not<Self, S>
+    where
+        Self: Sized,
+    {
+        combinator::Not::new_strict(self)
+    }
+
+    /// Combines this [`Filter`] with another [`Filter`], returning a filter
+    /// that enables spans and events if and only if *exactly one* of the two
+    /// filters would enable them.
+    ///
+    /// Because the result always depends on both sides, `xor` cannot
+    /// short-circuit at callsite-registration time the way [`and`] and
+    /// [`or`] sometimes can; its [`callsite_enabled`] only returns a static
+    /// `Interest` when both filters are themselves static, and otherwise
+    /// downgrades to [`Interest::sometimes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tracing_subscriber::filter::{filter_fn, FilterExt};
+    ///
+    /// let a = filter_fn(|meta| meta.target().starts_with("a"));
+    /// let b = filter_fn(|meta| meta.level() <= &tracing::Level::INFO);
+    ///
+    /// // Enabled if the target starts with "a" *or* the level is INFO or
+    /// // lower, but *not* if both are true.
+    /// let filter = a.xor(b);
+    /// ```
+    ///
+    /// [`Filter`]: crate::subscribe::Filter
+    /// [`and`]: FilterExt::and
+    /// [`or`]: FilterExt::or
+    /// [`callsite_enabled`]: crate::subscribe::Filter::callsite_enabled
+    /// [`Interest::sometimes`]: tracing_core::collect::Interest::sometimes
+    fn xor<B>(self, other: B) -> combinator::Xor<Self, B, S>
+    where
+        Self: Sized,
+        B: subscribe::Filter<S>,
+    {
+        combinator::Xor::new(self, other)
+    }
+
+    /// Applies `self` only to spans/events matched by `predicate`, and
+    /// otherwise falls through to enabling them unconditionally.
+    ///
+    /// This is a short-circuiting conditional combinator: `predicate` is
+    /// consulted first, and `self` (the "then" filter) is only consulted —
+    /// and only determines the outcome — for spans/events the predicate
+    /// matches. Combine this with [`and`] if you want the fallthrough case
+    /// to be disabled rather than enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tracing_subscriber::filter::{filter_fn, LevelFilter, FilterExt};
+    ///
+    /// // Only apply the INFO level filter to the "noisy_crate" target;
+    /// // everything else is left enabled.
+    /// let filter = LevelFilter::INFO.when(filter_fn(|meta| meta.target().starts_with("noisy_crate")));
+    /// ```
+    ///
+    /// [`and`]: FilterExt::and
+    fn when<P>(self, predicate: P) -> combinator::When<P, Self, S>
+    where
+        Self: Sized,
+        P: subscribe::Filter<S>,
+    {
+        combinator::When::new(predicate, self)
+    }
+
+    /// Combines this [`Filter`] with a deterministic, per-callsite sampling
+    /// filter, so that only 1 out of every `n` spans/events this filter
+    /// would otherwise enable are actually enabled.
+    ///
+    /// This is useful for downsampling high-volume callsites (for instance,
+    /// a `DEBUG` span emitted in a hot loop) without disabling them
+    /// entirely. Each callsite is sampled independently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tracing_subscriber::filter::{filter_fn, FilterExt};
+    ///
+    /// // Enables 1 out of every 100 events with the `noisy` target:
+    /// let filter = filter_fn(|meta| meta.target() == "noisy").sample_rate(100);
+    /// ```
+    ///
+    /// [`Filter`]: crate::subscribe::Filter
+    fn sample_rate(self, n: u64) -> combinator::And<Self, combinator::Sampling<S>, S>
+    where
+        Self: Sized,
+    {
+        self.and(combinator::Sampling::every_nth(n))
+    }
+
+    /// Combines this [`Filter`] with a per-callsite, time-based rate-limiting
+    /// filter, enabling at most `events_per_sec` spans/events (with bursts up
+    /// to `burst`) per second for each callsite this filter would otherwise
+    /// enable.
+    ///
+    /// [`Filter`]: crate::subscribe::Filter
+    fn sample_rate_per_second(
+        self,
+        events_per_sec: f64,
+        burst: f64,
+    ) -> combinator::And<Self, combinator::Sampling<S>, S>
+    where
+        Self: Sized,
+    {
+        self.and(combinator::Sampling::per_second(events_per_sec, burst))
+    }
+
     /// [Boxes] `self`, erasing its concrete type.
     ///
     /// This is equivalent to calling [`Box::new`], but in method form, so that
@@ -629,9 +1071,24 @@ impl<S, F, C> Filtered<S, F, C> {
         }
     }
 
+    /// Borrows this `Filtered` subscriber's `FilterId`, without cloning it.
+    ///
+    /// Once a `FilterId` spills onto the heap (`Bitset::Spilled`, past 64
+    /// per-subscriber filters), cloning it allocates --- and almost every
+    /// call site below only needs to read the id, not own a copy of it.
+    /// Prefer this over [`Self::filter_id`] unless the call genuinely needs
+    /// an owned `FilterId` (e.g. to move into a new [`Context`]).
     #[inline(always)]
-    fn id(&self) -> FilterId {
-        self.id.0
+    fn id(&self) -> &FilterId {
+        &self.id.0
+    }
+
+    /// Clones this `Filtered` subscriber's `FilterId`, for the few call
+    /// sites that need to hand an owned copy to something like
+    /// [`Context::with_filter`] or [`Context::if_enabled_for`].
+    #[inline(always)]
+    fn filter_id(&self) -> FilterId {
+        self.id.0.clone()
     }
 
     fn did_enable(&self, f: impl FnOnce()) {
@@ -761,7 +1218,7 @@ where
     }
 
     fn enabled(&self, metadata: &Metadata<'_>, cx: Context<'_, C>) -> bool {
-        let cx = cx.with_filter(self.id());
+        let cx = cx.with_filter(self.filter_id());
         let enabled = self.filter.enabled(metadata, &cx);
         FILTERING.with(|filtering| filtering.set(self.id(), enabled));
 
@@ -789,7 +1246,7 @@ where
 
     fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, cx: Context<'_, C>) {
         self.did_enable(|| {
-            let cx = cx.with_filter(self.id());
+            let cx = cx.with_filter(self.filter_id());
             self.filter.on_new_span(attrs, id, cx.clone());
             self.subscriber.on_new_span(attrs, id, cx);
         })
@@ -801,7 +1258,7 @@ where
     }
 
     fn on_record(&self, span: &span::Id, values: &span::Record<'_>, cx: Context<'_, C>) {
-        if let Some(cx) = cx.if_enabled_for(span, self.id()) {
+        if let Some(cx) = cx.if_enabled_for(span, self.filter_id()) {
             self.filter.on_record(span, values, cx.clone());
             self.subscriber.on_record(span, values, cx);
         }
@@ -811,12 +1268,12 @@ where
         // only call `on_follows_from` if both spans are enabled by us
         if cx.is_enabled_for(span, self.id()) && cx.is_enabled_for(follows, self.id()) {
             self.subscriber
-                .on_follows_from(span, follows, cx.with_filter(self.id()))
+                .on_follows_from(span, follows, cx.with_filter(self.filter_id()))
         }
     }
 
     fn event_enabled(&self, event: &Event<'_>, cx: Context<'_, C>) -> bool {
-        let cx = cx.with_filter(self.id());
+        let cx = cx.with_filter(self.filter_id());
         let enabled = FILTERING
             .with(|filtering| filtering.and(self.id(), || self.filter.event_enabled(event, &cx)));
 
@@ -833,26 +1290,26 @@ where
 
     fn on_event(&self, event: &Event<'_>, cx: Context<'_, C>) {
         self.did_enable(|| {
-            self.subscriber.on_event(event, cx.with_filter(self.id()));
+            self.subscriber.on_event(event, cx.with_filter(self.filter_id()));
         })
     }
 
     fn on_enter(&self, id: &span::Id, cx: Context<'_, C>) {
-        if let Some(cx) = cx.if_enabled_for(id, self.id()) {
+        if let Some(cx) = cx.if_enabled_for(id, self.filter_id()) {
             self.filter.on_enter(id, cx.clone());
             self.subscriber.on_enter(id, cx);
         }
     }
 
     fn on_exit(&self, id: &span::Id, cx: Context<'_, C>) {
-        if let Some(cx) = cx.if_enabled_for(id, self.id()) {
+        if let Some(cx) = cx.if_enabled_for(id, self.filter_id()) {
             self.filter.on_exit(id, cx.clone());
             self.subscriber.on_exit(id, cx);
         }
     }
 
     fn on_close(&self, id: span::Id, cx: Context<'_, C>) {
-        if let Some(cx) = cx.if_enabled_for(&id, self.id()) {
+        if let Some(cx) = cx.if_enabled_for(&id, self.filter_id()) {
             self.filter.on_close(id.clone(), cx.clone());
             self.subscriber.on_close(id, cx);
         }
@@ -860,7 +1317,7 @@ where
 
     // XXX(eliza): the existence of this method still makes me sad...
     fn on_id_change(&self, old: &span::Id, new: &span::Id, cx: Context<'_, C>) {
-        if let Some(cx) = cx.if_enabled_for(old, self.id()) {
+        if let Some(cx) = cx.if_enabled_for(old, self.filter_id()) {
             self.subscriber.on_id_change(old, new, cx)
         }
     }
@@ -898,17 +1355,34 @@ where
 
 impl FilterId {
     const fn disabled() -> Self {
-        Self(u64::MAX)
+        Self {
+            bits: Bitset::new(),
+            disabled: true,
+        }
     }
 
     /// Returns a `FilterId` that will consider _all_ spans enabled.
     pub(crate) const fn none() -> Self {
-        Self(0)
+        Self {
+            bits: Bitset::new(),
+            disabled: false,
+        }
     }
 
-    pub(crate) fn new(id: u8) -> Self {
-        assert!(id < 64, "filter IDs may not be greater than 64");
-        Self(1 << id as usize)
+    /// Constructs a new `FilterId` for the filter registered with the given
+    /// `id`.
+    ///
+    /// Unlike the original `u64`-backed implementation, `id` is no longer
+    /// bounded at 64: the backing [`Bitset`] spills onto the heap once a
+    /// filter past bit 64 is registered, so a collector may host as many
+    /// per-subscriber filters as it has memory for.
+    pub(crate) fn new(id: usize) -> Self {
+        let word = id / Bitset::BITS_PER_WORD;
+        let bit = (id % Bitset::BITS_PER_WORD) as u32;
+        Self {
+            bits: Bitset::single(word, bit),
+            disabled: false,
+        }
     }
 
     /// Combines two `FilterId`s, returning a new `FilterId` that will match a
@@ -989,21 +1463,26 @@ impl FilterId {
     /// ```
     ///
     /// [`Context`]: crate::subscribe::Context
-    pub(crate) fn and(self, FilterId(other): Self) -> Self {
+    pub(crate) fn and(self, other: Self) -> Self {
         // If this mask is disabled, just return the other --- otherwise, we
         // would always see that every span is disabled.
-        if self.0 == Self::disabled().0 {
-            return Self(other);
+        if self.disabled {
+            return other;
         }
 
-        Self(self.0 | other)
+        let mut bits = self.bits;
+        bits.or_with(&other.bits);
+        Self {
+            bits,
+            disabled: false,
+        }
     }
 }
 
 impl fmt::Debug for FilterId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // don't print a giant set of the numbers 0..63 if the filter ID is disabled.
-        if self.0 == Self::disabled().0 {
+        if self.disabled {
             return f
                 .debug_tuple("FilterId")
                 .field(&format_args!("DISABLED"))
@@ -1012,11 +1491,13 @@ impl fmt::Debug for FilterId {
 
         if f.alternate() {
             f.debug_struct("FilterId")
-                .field("ids", &format_args!("{:?}", FmtBitset(self.0)))
-                .field("bits", &format_args!("{:b}", self.0))
+                .field("ids", &format_args!("{:?}", FmtBitset(&self.bits)))
+                .field("bits", &format_args!("{:b}", FmtBinary(&self.bits)))
                 .finish()
         } else {
-            f.debug_tuple("FilterId").field(&FmtBitset(self.0)).finish()
+            f.debug_tuple("FilterId")
+                .field(&FmtBitset(&self.bits))
+                .finish()
         }
     }
 }
@@ -1024,7 +1505,7 @@ impl fmt::Debug for FilterId {
 impl fmt::Binary for FilterId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("FilterId")
-            .field(&format_args!("{:b}", self.0))
+            .field(&format_args!("{:b}", FmtBinary(&self.bits)))
             .finish()
     }
 }
@@ -1036,30 +1517,106 @@ impl<F, S> FilterExt<S> for F where F: subscribe::Filter<S> {}
 // === impl FilterMap ===
 
 impl FilterMap {
-    pub(crate) fn set(self, FilterId(mask): FilterId, enabled: bool) -> Self {
-        if mask == u64::MAX {
+    pub(crate) fn set(self, filter: &FilterId, enabled: bool) -> Self {
+        if filter.disabled {
             return self;
         }
 
+        let mut bits = self.bits;
         if enabled {
-            Self {
-                bits: self.bits & (!mask),
-            }
+            bits.and_not(&filter.bits);
         } else {
-            Self {
-                bits: self.bits | mask,
-            }
+            bits.or_with(&filter.bits);
         }
+        Self { bits }
     }
 
     #[inline]
-    pub(crate) fn is_enabled(self, FilterId(mask): FilterId) -> bool {
-        self.bits & mask == 0
+    pub(crate) fn is_enabled(self, filter: &FilterId) -> bool {
+        !self.bits.intersects(&filter.bits)
     }
 
     #[inline]
     pub(crate) fn any_enabled(self) -> bool {
-        self.bits != u64::MAX
+        !self.bits.is_all_ones()
+    }
+}
+
+// === impl FilterOutcome ===
+
+#[cfg(feature = "registry")]
+impl FilterOutcome {
+    /// Returns a snapshot of the current thread's per-subscriber filter
+    /// outcome.
+    ///
+    /// This reflects the `enabled`/`event_enabled` decisions made so far
+    /// during the current filtering pass by whichever [`Filtered`]
+    /// subscribers in the stack have already run; it's intended to be
+    /// called from within another `Filtered` subscriber's own `enabled` or
+    /// `event_enabled` method, so that earlier subscribers in the stack
+    /// have already recorded their decisions.
+    ///
+    /// [`Filtered`]: crate::filter::subscriber_filters::Filtered
+    pub fn current() -> Self {
+        Self {
+            map: FILTERING.with(|filtering| filtering.filter_map()),
+        }
+    }
+
+    /// Returns `true` if the filter identified by `id` has *not* disabled
+    /// the current span/event (i.e. it either enabled it, or hasn't run
+    /// yet).
+    pub fn is_enabled_by(&self, id: &FilterId) -> bool {
+        self.map.clone().is_enabled(id)
+    }
+
+    /// Returns an iterator over the [`FilterId`]s, among those registered
+    /// with an id less than `registered`, that disabled the current
+    /// span/event.
+    ///
+    /// `registered` should be the number of per-subscriber filters that have
+    /// been registered on the collector being introspected (e.g. via
+    /// [`Collect::register_filter`][register_filter]) --- a `FilterOutcome`
+    /// snapshot doesn't otherwise know how many filters exist, since an
+    /// unset bit means either "enabled" or "never registered".
+    ///
+    /// [register_filter]: tracing_core::Collect::register_filter
+    pub fn disabled_by(&self, registered: usize) -> DisabledBy<'_> {
+        DisabledBy {
+            outcome: self,
+            next: 0,
+            registered,
+        }
+    }
+}
+
+/// An iterator over the [`FilterId`]s that disabled the span/event snapshotted
+/// by a [`FilterOutcome`].
+///
+/// This is returned by [`FilterOutcome::disabled_by`]. See that method's
+/// documentation for details.
+#[cfg(feature = "registry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "registry")))]
+#[derive(Debug)]
+pub struct DisabledBy<'a> {
+    outcome: &'a FilterOutcome,
+    next: usize,
+    registered: usize,
+}
+
+#[cfg(feature = "registry")]
+impl Iterator for DisabledBy<'_> {
+    type Item = FilterId;
+
+    fn next(&mut self) -> Option<FilterId> {
+        while self.next < self.registered {
+            let id = FilterId::new(self.next);
+            self.next += 1;
+            if !self.outcome.is_enabled_by(&id) {
+                return Some(id);
+            }
+        }
+        None
     }
 }
 
@@ -1067,10 +1624,13 @@ impl fmt::Debug for FilterMap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let alt = f.alternate();
         let mut s = f.debug_struct("FilterMap");
-        s.field("disabled_by", &format_args!("{:?}", &FmtBitset(self.bits)));
+        s.field(
+            "disabled_by",
+            &format_args!("{:?}", &FmtBitset(&self.bits)),
+        );
 
         if alt {
-            s.field("bits", &format_args!("{:b}", self.bits));
+            s.field("bits", &format_args!("{:b}", FmtBinary(&self.bits)));
         }
 
         s.finish()
@@ -1080,7 +1640,7 @@ impl fmt::Debug for FilterMap {
 impl fmt::Binary for FilterMap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FilterMap")
-            .field("bits", &format_args!("{:b}", self.bits))
+            .field("bits", &format_args!("{:b}", FmtBinary(&self.bits)))
             .finish()
     }
 }
@@ -1090,20 +1650,22 @@ impl fmt::Binary for FilterMap {
 impl FilterState {
     const fn new() -> Self {
         Self {
-            enabled: Cell::new(FilterMap::new()),
+            enabled: RefCell::new(FilterMap::new()),
             interest: RefCell::new(None),
+            not_state: RefCell::new(Vec::new()),
+            when_state: RefCell::new(Vec::new()),
 
             #[cfg(debug_assertions)]
             counters: DebugCounters::new(),
         }
     }
 
-    fn set(&self, filter: FilterId, enabled: bool) {
+    fn set(&self, filter: &FilterId, enabled: bool) {
         #[cfg(debug_assertions)]
         {
             let in_current_pass = self.counters.in_filter_pass.get();
             if in_current_pass == 0 {
-                debug_assert_eq!(self.enabled.get(), FilterMap::new());
+                debug_assert_eq!(*self.enabled.borrow(), FilterMap::new());
             }
             self.counters.in_filter_pass.set(in_current_pass + 1);
             debug_assert_eq!(
@@ -1113,7 +1675,8 @@ impl FilterState {
             )
         }
 
-        self.enabled.set(self.enabled.get().set(filter, enabled))
+        let next = self.enabled.borrow().clone().set(filter, enabled);
+        *self.enabled.borrow_mut() = next;
     }
 
     fn add_interest(&self, interest: Interest) {
@@ -1144,11 +1707,11 @@ impl FilterState {
     pub(crate) fn event_enabled() -> bool {
         FILTERING
             .try_with(|this| {
-                let enabled = this.enabled.get().any_enabled();
+                let enabled = this.enabled.borrow().any_enabled();
                 #[cfg(debug_assertions)]
                 {
                     if this.counters.in_filter_pass.get() == 0 {
-                        debug_assert_eq!(this.enabled.get(), FilterMap::new());
+                        debug_assert_eq!(*this.enabled.borrow(), FilterMap::new());
                     }
 
                     // Nothing enabled this event, we won't tick back down the
@@ -1167,8 +1730,8 @@ impl FilterState {
     ///
     /// This is used to implement the `on_event` and `new_span` methods for
     /// `Filtered`.
-    fn did_enable(&self, filter: FilterId, f: impl FnOnce()) {
-        let map = self.enabled.get();
+    fn did_enable(&self, filter: &FilterId, f: impl FnOnce()) {
+        let map = self.enabled.borrow().clone();
         if map.is_enabled(filter) {
             // If the filter didn't disable the current span/event, run the
             // callback.
@@ -1179,13 +1742,13 @@ impl FilterState {
             // `FilterState`. The bit has already been "consumed" by skipping
             // this callback, and we need to ensure that the `FilterMap` for
             // this thread is reset when the *next* `enabled` call occurs.
-            self.enabled.set(map.set(filter, true));
+            *self.enabled.borrow_mut() = map.set(filter, true);
         }
         #[cfg(debug_assertions)]
         {
             let in_current_pass = self.counters.in_filter_pass.get();
             if in_current_pass <= 1 {
-                debug_assert_eq!(self.enabled.get(), FilterMap::new());
+                debug_assert_eq!(*self.enabled.borrow(), FilterMap::new());
             }
             self.counters
                 .in_filter_pass
@@ -1199,10 +1762,10 @@ impl FilterState {
     }
 
     /// Run a second filtering pass, e.g. for Subscribe::event_enabled.
-    fn and(&self, filter: FilterId, f: impl FnOnce() -> bool) -> bool {
-        let map = self.enabled.get();
+    fn and(&self, filter: &FilterId, f: impl FnOnce() -> bool) -> bool {
+        let map = self.enabled.borrow().clone();
         let enabled = map.is_enabled(filter) && f();
-        self.enabled.set(map.set(filter, enabled));
+        *self.enabled.borrow_mut() = map.set(filter, enabled);
         enabled
     }
 
@@ -1215,13 +1778,74 @@ impl FilterState {
         // a panic and the thread-local has been torn down, that's fine, just
         // ignore it ratehr than panicking.
         let _ = FILTERING.try_with(|filtering| {
-            filtering.enabled.set(FilterMap::new());
+            *filtering.enabled.borrow_mut() = FilterMap::new();
+            filtering.not_state.borrow_mut().clear();
+            filtering.when_state.borrow_mut().clear();
 
             #[cfg(debug_assertions)]
             filtering.counters.in_filter_pass.set(0);
         });
     }
 
+    /// Stashes the result of a strict `combinator::Not`'s `enabled` call,
+    /// keyed by that `Not` instance's own `NotId`, so that the matching
+    /// `event_enabled` call can fold it into a true `!(enabled() &&
+    /// event_enabled())`.
+    pub(crate) fn stash_not_result(id: combinator::NotId, enabled: bool) {
+        let _ = FILTERING.try_with(|filtering| {
+            let mut not_state = filtering.not_state.borrow_mut();
+            match not_state.iter_mut().find(|(existing, _)| *existing == id) {
+                Some((_, slot)) => *slot = enabled,
+                None => not_state.push((id, enabled)),
+            }
+        });
+    }
+
+    /// Takes back the result previously stashed by `stash_not_result` for the
+    /// given `Not` instance, if any.
+    ///
+    /// The entry is removed, since a strict `Not`'s `enabled` call is always
+    /// immediately followed by at most one corresponding `event_enabled`
+    /// call for the same span/event.
+    pub(crate) fn take_not_result(id: combinator::NotId) -> Option<bool> {
+        FILTERING
+            .try_with(|filtering| {
+                let mut not_state = filtering.not_state.borrow_mut();
+                let idx = not_state.iter().position(|(existing, _)| *existing == id)?;
+                Some(not_state.swap_remove(idx).1)
+            })
+            .ok()?
+    }
+
+    /// Stashes whether a `combinator::When`'s `predicate` applied during its
+    /// `enabled` call, keyed by that `When` instance's own `WhenId`, so that
+    /// the matching `event_enabled` call can reuse the decision.
+    pub(crate) fn stash_when_applies(id: combinator::WhenId, applies: bool) {
+        let _ = FILTERING.try_with(|filtering| {
+            let mut when_state = filtering.when_state.borrow_mut();
+            match when_state.iter_mut().find(|(existing, _)| *existing == id) {
+                Some((_, slot)) => *slot = applies,
+                None => when_state.push((id, applies)),
+            }
+        });
+    }
+
+    /// Takes back the decision previously stashed by `stash_when_applies`
+    /// for the given `When` instance, if any.
+    ///
+    /// The entry is removed, since a `When`'s `enabled` call is always
+    /// immediately followed by at most one corresponding `event_enabled`
+    /// call for the same event.
+    pub(crate) fn take_when_applies(id: combinator::WhenId) -> Option<bool> {
+        FILTERING
+            .try_with(|filtering| {
+                let mut when_state = filtering.when_state.borrow_mut();
+                let idx = when_state.iter().position(|(existing, _)| *existing == id)?;
+                Some(when_state.swap_remove(idx).1)
+            })
+            .ok()?
+    }
+
     pub(crate) fn take_interest() -> Option<Interest> {
         FILTERING
             .try_with(|filtering| {
@@ -1238,7 +1862,7 @@ impl FilterState {
     }
 
     pub(crate) fn filter_map(&self) -> FilterMap {
-        let map = self.enabled.get();
+        let map = self.enabled.borrow().clone();
         #[cfg(debug_assertions)]
         if self.counters.in_filter_pass.get() == 0 {
             debug_assert_eq!(map, FilterMap::new());
@@ -1269,7 +1893,7 @@ impl FilterState {
 /// existing `FilterId` field, since it won't make the struct any bigger.
 ///
 /// Don't worry, this isn't on the test. :)
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 #[repr(transparent)]
 struct MagicPsfDowncastMarker(FilterId);
 impl fmt::Debug for MagicPsfDowncastMarker {
@@ -1309,17 +1933,38 @@ where
     .is_some()
 }
 
-struct FmtBitset(u64);
+struct FmtBitset<'a>(&'a Bitset);
 
-impl fmt::Debug for FmtBitset {
+impl fmt::Debug for FmtBitset<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut set = f.debug_set();
-        for bit in 0..64 {
-            // if the `bit`-th bit is set, add it to the debug set
-            if self.0 & (1 << bit) != 0 {
-                set.entry(&bit);
+        for word_idx in 0..self.0.words().len() {
+            for bit in 0..Bitset::BITS_PER_WORD {
+                // if the `bit`-th bit is set, add its global index to the
+                // debug set.
+                if self.0.is_set(word_idx, bit as u32) {
+                    set.entry(&(word_idx * Bitset::BITS_PER_WORD + bit));
+                }
             }
         }
         set.finish()
     }
 }
+
+/// Formats a [`Bitset`]'s words as a binary literal, most-significant word
+/// first, matching the historical single-`u64` `{:b}` output for the common
+/// (inline) case.
+struct FmtBinary<'a>(&'a Bitset);
+
+impl fmt::Binary for FmtBinary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words = self.0.words();
+        for (i, word) in words.iter().enumerate().rev() {
+            if i != words.len() - 1 {
+                write!(f, "_")?;
+            }
+            write!(f, "{:b}", word)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+//! Utilities for implementing and composing [`tracing`] collectors.
+//!
+//! [`tracing`]: https://docs.rs/tracing
+
+pub mod field;
+pub mod filter;
+pub mod registry;
+pub mod reload;
+pub mod report;
+pub mod subscribe;
+
+/// A lightweight, manually-recorded alternative to capturing a full
+/// backtrace when an error is propagated; see the [module-level
+/// docs](track) for details.
+#[cfg(feature = "track")]
+#[cfg_attr(docsrs, doc(cfg(feature = "track")))]
+pub mod track;
@@ -0,0 +1,493 @@
+//! A [`Subscribe`] that reports high-severity events to an external
+//! error-tracking service, in the style of crash/error-report backends like
+//! Sentry or Bugsnag.
+//!
+//! [`ReportingLayer`] watches every event so it can keep a rolling
+//! "breadcrumb trail" of recent lower-severity events per thread, but only
+//! actually builds and transmits a [`Report`] when an event at or above its
+//! configured [`LevelFilter`] fires (and survives its sample rate). A report
+//! is built on the thread that produced the triggering event, then handed
+//! off to a background thread so that batching and sending it never blocks
+//! that thread, and is sent
+//! through a [`ReportTransport`] so the HTTP client (or a test fake) is
+//! pluggable.
+//!
+//! [`Subscribe`]: crate::subscribe::Subscribe
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use tracing_core::field::{Field, Visit};
+use tracing_core::{collect::Collect, span, Event};
+
+use crate::field;
+use crate::filter::LevelFilter;
+use crate::registry::LookupSpan;
+use crate::subscribe::{Context, Subscribe};
+
+/// A single recorded field's value.
+///
+/// This is kept as a small owned enum, rather than reaching for
+/// `serde_json::Value`, so that building a [`Report`] doesn't pull in
+/// `serde_json` as a hard dependency for users who never enable this
+/// crate's `serde` feature. Enabling `serde` derives [`serde::Serialize`]
+/// for this type (and for [`Breadcrumb`], [`ReportSpan`], and [`Report`]),
+/// the same way [`TrackPoint`](crate::track::TrackPoint) opts into `serde`
+/// support.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum FieldValue {
+    /// A value recorded via [`Visit::record_f64`].
+    F64(f64),
+    /// A value recorded via [`Visit::record_i64`].
+    I64(i64),
+    /// A value recorded via [`Visit::record_u64`].
+    U64(u64),
+    /// A value recorded via [`Visit::record_bool`].
+    Bool(bool),
+    /// A value recorded via [`Visit::record_str`] or [`Visit::record_debug`]
+    /// (the latter capped by [`field::depth::capture_debug`]).
+    Str(String),
+}
+
+impl FieldValue {
+    /// Returns this value as a `&str`, if it's a [`FieldValue::Str`].
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// A single span or event's recorded fields.
+pub type Fields = BTreeMap<String, FieldValue>;
+
+/// A lower-severity event recorded shortly before a [`Report`] was
+/// triggered, kept around to give the report context.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Breadcrumb {
+    /// When this breadcrumb was recorded.
+    pub timestamp: SystemTime,
+    /// The breadcrumb event's level, e.g. `"INFO"`.
+    pub level: String,
+    /// The breadcrumb event's target.
+    pub target: String,
+    /// The breadcrumb event's recorded fields.
+    pub fields: Fields,
+}
+
+/// A single open span in a [`Report`]'s ancestry, from the root span down to
+/// the span the triggering event was recorded in.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReportSpan {
+    /// The span's name.
+    pub name: &'static str,
+    /// The span's recorded fields.
+    pub fields: Fields,
+}
+
+/// A structured report of a high-severity event, built by [`ReportingLayer`]
+/// and handed to a [`ReportTransport`] for delivery.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Report {
+    /// The release/version string this `ReportingLayer` was constructed
+    /// with.
+    pub release: String,
+    /// When the triggering event was recorded.
+    pub timestamp: SystemTime,
+    /// The triggering event's level, e.g. `"ERROR"`.
+    pub level: String,
+    /// The triggering event's target.
+    pub target: String,
+    /// The triggering event's recorded fields.
+    pub fields: Fields,
+    /// The ancestry of spans the triggering event was recorded in, from
+    /// root to leaf.
+    pub spans: Vec<ReportSpan>,
+    /// Recent lower-severity events on the same thread, oldest first, kept
+    /// for context.
+    pub breadcrumbs: Vec<Breadcrumb>,
+}
+
+/// Delivers batches of [`Report`]s somewhere --- typically by POSTing them as
+/// JSON to an HTTP endpoint.
+///
+/// Implement this to plug in a real HTTP client, or an in-memory fake for
+/// testing [`ReportingLayer`] without making network calls.
+pub trait ReportTransport: Send + Sync + 'static {
+    /// Delivers a batch of reports.
+    ///
+    /// This runs on `ReportingLayer`'s dedicated background thread, so it's
+    /// fine for this to block.
+    fn send_batch(&self, reports: Vec<Report>);
+}
+
+/// A visitor that records an event or span's fields as a [`Fields`] map.
+///
+/// `record_debug` is the one field kind whose rendering can blow up in size
+/// for a pathologically deep or wide value (everything else records an
+/// already-bounded scalar), so it's the one capped by `max_depth` via
+/// [`field::depth::capture_debug`] rather than recorded as-is.
+struct FieldsVisitor {
+    fields: Fields,
+    max_depth: field::depth::DepthLimit,
+}
+
+impl FieldsVisitor {
+    fn new(fields: Fields, max_depth: field::depth::DepthLimit) -> Self {
+        Self { fields, max_depth }
+    }
+
+    fn into_fields(self) -> Fields {
+        self.fields
+    }
+}
+
+impl Visit for FieldsVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields
+            .insert(field.name().to_string(), FieldValue::F64(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields
+            .insert(field.name().to_string(), FieldValue::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields
+            .insert(field.name().to_string(), FieldValue::U64(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields
+            .insert(field.name().to_string(), FieldValue::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields
+            .insert(field.name().to_string(), FieldValue::Str(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let rendered = field::depth::capture_debug(value, self.max_depth);
+        self.fields
+            .insert(field.name().to_string(), FieldValue::Str(rendered));
+    }
+}
+
+/// Fields recorded for a span, stashed in that span's extensions by
+/// [`ReportingLayer::on_new_span`] and [`ReportingLayer::on_record`] so they
+/// can be read back when building a [`Report`]'s span ancestry.
+#[derive(Clone, Default)]
+struct SpanFields(Fields);
+
+/// A deterministic approximation of probabilistic sampling, used instead of
+/// pulling in a `rand` dependency for what's just a "send roughly this
+/// fraction of reports" knob.
+///
+/// This accumulates `rate` into a shared counter on every call, and fires
+/// whenever the accumulator crosses a whole number --- e.g. a rate of `0.3`
+/// fires on the ~3rd, ~7th, ~10th, ... call out of every 10. This is the same
+/// trick as a graphics line-drawing (Bresenham) algorithm, applied to time
+/// instead of pixels.
+struct SampleRate {
+    rate: f64,
+    // Stored as `accumulator * SCALE` in an integer so the shared counter can
+    // be a plain `AtomicU64` rather than needing a `Mutex<f64>`.
+    accumulator: AtomicU64,
+}
+
+impl SampleRate {
+    // 2^32, used as the fixed-point scale for `accumulator`.
+    const SCALE: f64 = 4_294_967_296.0;
+
+    fn new(rate: f64) -> Self {
+        Self {
+            rate: rate.clamp(0.0, 1.0),
+            accumulator: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns `true` if this call should be sampled.
+    fn sample(&self) -> bool {
+        if self.rate <= 0.0 {
+            return false;
+        }
+        if self.rate >= 1.0 {
+            return true;
+        }
+
+        let step = (self.rate * Self::SCALE) as u64;
+        let prev = self.accumulator.fetch_add(step, Ordering::Relaxed);
+        let next = prev.wrapping_add(step);
+        // We crossed a whole number (a multiple of `SCALE`) if the integer
+        // part changed.
+        (prev / Self::SCALE as u64) != (next / Self::SCALE as u64)
+    }
+}
+
+/// A process-wide unique identifier for a single [`ReportingLayer`]
+/// instance, used to key its slot in the per-thread [`BREADCRUMBS`] map.
+///
+/// `Subscribe`/`Filter` composability means more than one `ReportingLayer`
+/// can be stacked on the same `Registry` (e.g. one reporting to Sentry at
+/// `WARN` and another to an internal sink at `ERROR`). Keying breadcrumb
+/// storage by this id, rather than sharing one ring for every
+/// `ReportingLayer` on a thread, keeps each instance's breadcrumbs and
+/// `breadcrumb_capacity` from clobbering the other's.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct ReportingLayerId(usize);
+
+impl ReportingLayerId {
+    fn next() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A [`Subscribe`] that batches high-severity events into [`Report`]s and
+/// hands them off to a [`ReportTransport`] on a background thread.
+///
+/// Construct one with [`ReportingLayer::new`], supplying the release string,
+/// minimum level to report at, and sample rate as constructor parameters ---
+/// these are expected to vary per deployment/environment, so they're
+/// deliberately not compile-time constants.
+pub struct ReportingLayer<T> {
+    sender: Sender<Report>,
+    min_level: LevelFilter,
+    release: String,
+    sample_rate: SampleRate,
+    breadcrumb_capacity: usize,
+    max_field_depth: field::depth::DepthLimit,
+    // This instance's own identity, used to key its slot in `BREADCRUMBS` so
+    // that stacking more than one `ReportingLayer` doesn't have them share
+    // --- and clobber --- the same breadcrumb ring. See
+    // `ReportingLayerId`'s documentation.
+    id: ReportingLayerId,
+    _transport: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T: ReportTransport> ReportingLayer<T> {
+    /// Constructs a new `ReportingLayer`.
+    ///
+    /// `release` is attached to every report (e.g. a git SHA or semver
+    /// string), `min_level` is the lowest-severity level that triggers a
+    /// report (lower-severity events are still kept as breadcrumbs),
+    /// `sample_rate` is the fraction (`0.0..=1.0`) of triggering events that
+    /// are actually reported, and `batch_interval` bounds how long a report
+    /// can sit before being flushed to `transport`.
+    pub fn new(
+        transport: T,
+        release: impl Into<String>,
+        min_level: LevelFilter,
+        sample_rate: f64,
+        batch_interval: Duration,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel::<Report>();
+        let transport = Arc::new(transport);
+        thread::Builder::new()
+            .name("tracing-report-batcher".into())
+            .spawn(move || Self::run_batcher(receiver, transport, batch_interval))
+            .expect("failed to spawn tracing-subscriber report batcher thread");
+
+        Self {
+            sender,
+            min_level,
+            release: release.into(),
+            sample_rate: SampleRate::new(sample_rate),
+            breadcrumb_capacity: 32,
+            max_field_depth: field::depth::DepthLimit::DEFAULT,
+            id: ReportingLayerId::next(),
+            _transport: std::marker::PhantomData,
+        }
+    }
+
+    /// Overrides the number of lower-severity breadcrumbs kept per thread
+    /// (32, by default).
+    pub fn with_breadcrumb_capacity(mut self, capacity: usize) -> Self {
+        self.breadcrumb_capacity = capacity;
+        self
+    }
+
+    /// Overrides how deeply nested a `record_debug`-recorded field's
+    /// `Debug` output may be before it's truncated
+    /// ([`DepthLimit::DEFAULT`](field::depth::DepthLimit::DEFAULT) by
+    /// default), so a pathologically deep or wide value can't make a single
+    /// field's rendered output --- and so the `Report`s batched for delivery
+    /// --- grow unboundedly.
+    pub fn with_max_field_depth(mut self, max_depth: field::depth::DepthLimit) -> Self {
+        self.max_field_depth = max_depth;
+        self
+    }
+
+    /// Runs on the dedicated background thread, batching reports until
+    /// either `batch_interval` elapses or the sender is dropped, then
+    /// flushing them to `transport`.
+    fn run_batcher(receiver: mpsc::Receiver<Report>, transport: Arc<T>, batch_interval: Duration) {
+        loop {
+            let mut batch = match receiver.recv_timeout(batch_interval) {
+                Ok(report) => vec![report],
+                Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            };
+
+            // Keep accumulating reports until `batch_interval` has actually
+            // elapsed since the first one arrived, rather than flushing as
+            // soon as the channel momentarily runs dry --- this is what
+            // actually debounces a burst of reports into one batch, instead
+            // of just opportunistically draining whatever had already queued
+            // up by the time `recv_timeout` returned.
+            let deadline = Instant::now() + batch_interval;
+            loop {
+                let remaining = match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) => remaining,
+                    None => break,
+                };
+                match receiver.recv_timeout(remaining) {
+                    Ok(report) => batch.push(report),
+                    Err(mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        transport.send_batch(batch);
+                        return;
+                    }
+                }
+            }
+            transport.send_batch(batch);
+        }
+    }
+
+    fn record_breadcrumb(&self, event: &Event<'_>) {
+        let mut fields = FieldsVisitor::new(Fields::new(), self.max_field_depth);
+        event.record(&mut fields);
+
+        BREADCRUMBS.with(|breadcrumbs| {
+            let mut breadcrumbs = breadcrumbs.borrow_mut();
+            let breadcrumbs = breadcrumbs.entry(self.id).or_default();
+            breadcrumbs.push_back(Breadcrumb {
+                timestamp: SystemTime::now(),
+                level: event.metadata().level().to_string(),
+                target: event.metadata().target().to_string(),
+                fields: fields.into_fields(),
+            });
+            while breadcrumbs.len() > self.breadcrumb_capacity {
+                breadcrumbs.pop_front();
+            }
+        });
+    }
+}
+
+thread_local! {
+    // Keyed by `ReportingLayerId` rather than a single shared ring, so that
+    // stacking more than one `ReportingLayer` on a thread gives each its own
+    // breadcrumb trail and capacity instead of the two clobbering each
+    // other's. See `ReportingLayerId`'s documentation.
+    static BREADCRUMBS: std::cell::RefCell<HashMap<ReportingLayerId, VecDeque<Breadcrumb>>> =
+        std::cell::RefCell::new(HashMap::new());
+}
+
+impl<C, T> Subscribe<C> for ReportingLayer<T>
+where
+    C: Collect + for<'span> LookupSpan<'span>,
+    T: ReportTransport,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, C>) {
+        let span = ctx.span(id).expect("span must exist in on_new_span");
+        let mut fields = FieldsVisitor::new(Fields::new(), self.max_field_depth);
+        attrs.record(&mut fields);
+        span.extensions_mut().insert(SpanFields(fields.into_fields()));
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, C>) {
+        let span = ctx.span(id).expect("span must exist in on_record");
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            let mut visitor = FieldsVisitor::new(std::mem::take(fields), self.max_field_depth);
+            values.record(&mut visitor);
+            *fields = visitor.into_fields();
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, C>) {
+        let level = *event.metadata().level();
+        let is_triggering = LevelFilter::from_level(level) <= self.min_level;
+
+        // If this event might itself trigger a report, snapshot the ring
+        // *before* recording it as a breadcrumb below, so a triggering event
+        // doesn't end up as one of its own report's breadcrumbs.
+        let breadcrumbs = is_triggering.then(|| {
+            BREADCRUMBS.with(|breadcrumbs| {
+                breadcrumbs
+                    .borrow()
+                    .get(&self.id)
+                    .map(|breadcrumbs| breadcrumbs.iter().cloned().collect::<Vec<_>>())
+                    .unwrap_or_default()
+            })
+        });
+
+        // Every event --- not just ones we report --- becomes a breadcrumb
+        // for whatever report fires next on this thread.
+        self.record_breadcrumb(event);
+
+        let breadcrumbs = match breadcrumbs {
+            Some(breadcrumbs) => breadcrumbs,
+            None => return,
+        };
+        if !self.sample_rate.sample() {
+            return;
+        }
+
+        let mut fields = FieldsVisitor::new(Fields::new(), self.max_field_depth);
+        event.record(&mut fields);
+
+        let spans = ctx
+            .event_scope(event)
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| {
+                let fields = span
+                    .extensions()
+                    .get::<SpanFields>()
+                    .map(|f| f.0.clone())
+                    .unwrap_or_default();
+                ReportSpan {
+                    name: span.name(),
+                    fields,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let report = Report {
+            release: self.release.clone(),
+            timestamp: SystemTime::now(),
+            level: level.to_string(),
+            target: event.metadata().target().to_string(),
+            fields: fields.into_fields(),
+            spans,
+            breadcrumbs,
+        };
+
+        // If the batcher thread has gone away (e.g. it panicked), there's
+        // nothing more we can do; silently drop the report rather than
+        // panicking the instrumented application over a reporting failure.
+        let _ = self.sender.send(report);
+    }
+}
+
+impl<T> fmt::Debug for ReportingLayer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReportingLayer")
+            .field("release", &self.release)
+            .field("min_level", &self.min_level)
+            .finish()
+    }
+}
@@ -0,0 +1,310 @@
+//! A lightweight, manually-recorded alternative to capturing a full
+//! backtrace when an error is propagated.
+//!
+//! Instead of unwinding-based stack capture (which is comparatively
+//! expensive and unavailable in some `no_std`-ish environments), this module
+//! lets error types carry a [`Vec<TrackPoint>`](TrackPoint) "trail" that's
+//! extended one call site at a time via the [`track!`] macro, typically at
+//! every `?`-propagation site. [`TrackableLayer`] then surfaces that trail
+//! as a structured `track.history` field whenever a tracked error is
+//! recorded on an event.
+//!
+//! ```
+//! use tracing_subscriber::track::{self, Trackable, TrackPoint};
+//!
+//! #[derive(Debug)]
+//! struct MyError {
+//!     message: &'static str,
+//!     trail: Vec<TrackPoint>,
+//! }
+//!
+//! impl std::fmt::Display for MyError {
+//!     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+//!         write!(f, "{}", self.message)
+//!     }
+//! }
+//!
+//! impl std::error::Error for MyError {}
+//!
+//! impl Trackable for MyError {
+//!     fn track_trail_mut(&mut self) -> &mut Vec<TrackPoint> {
+//!         &mut self.trail
+//!     }
+//!
+//!     fn track_trail(&self) -> &[TrackPoint] {
+//!         &self.trail
+//!     }
+//! }
+//!
+//! fn inner() -> Result<(), MyError> {
+//!     Err(MyError { message: "oh no", trail: Vec::new() })
+//! }
+//!
+//! fn outer() -> Result<(), MyError> {
+//!     track::track(inner(), TrackPoint::new(file!(), line!(), module_path!(), None))?;
+//!     Ok(())
+//! }
+//!
+//! assert!(outer().is_err());
+//! ```
+use std::fmt;
+use std::marker::PhantomData;
+
+use tracing_core::collect::Collect;
+use tracing_core::field::{Field, Visit};
+use tracing_core::Event;
+
+use crate::subscribe::{Context, Subscribe};
+
+/// A single recorded call site in a [`Trackable`] error's trail.
+///
+/// Trails are recorded innermost (the site closest to the original failure)
+/// first, and [`fmt::Display`]ed in that order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackPoint {
+    /// The file in which this track point was recorded, as returned by
+    /// [`file!`].
+    pub file: &'static str,
+    /// The line on which this track point was recorded, as returned by
+    /// [`line!`].
+    pub line: u32,
+    /// The module path in which this track point was recorded, as returned
+    /// by [`module_path!`].
+    pub module: &'static str,
+    /// An optional, freeform message attached via the two-argument form of
+    /// [`track!`].
+    pub message: Option<String>,
+}
+
+impl TrackPoint {
+    /// Constructs a new `TrackPoint`. This is normally called by the
+    /// [`track!`] macro rather than directly.
+    pub fn new(
+        file: &'static str,
+        line: u32,
+        module: &'static str,
+        message: Option<String>,
+    ) -> Self {
+        Self {
+            file,
+            line,
+            module,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for TrackPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at {}:{} ({})", self.file, self.line, self.module)?;
+        if let Some(message) = &self.message {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+/// An error type that can carry a trail of [`TrackPoint`]s, recorded one
+/// call site at a time by the [`track!`] macro.
+///
+/// Implement this for an error type (typically alongside [`std::error::Error`])
+/// to opt into `track!`, and register a [`TrackableLayer<E>`] for that same
+/// error type to have its trail surfaced as a `track.history` field whenever
+/// it's recorded on a tracing event.
+pub trait Trackable {
+    /// Returns a mutable reference to this error's trail, so a new
+    /// [`TrackPoint`] can be appended to it.
+    fn track_trail_mut(&mut self) -> &mut Vec<TrackPoint>;
+
+    /// Returns this error's trail, innermost call site first.
+    fn track_trail(&self) -> &[TrackPoint];
+}
+
+/// Appends `point` to `result`'s trail if it's an `Err`, and returns the
+/// result unchanged.
+///
+/// This is called by the [`track!`] macro; prefer that macro over calling
+/// this directly, since it fills in `point` from the call site for you.
+pub fn track<T, E: Trackable>(result: Result<T, E>, point: TrackPoint) -> Result<T, E> {
+    result.map_err(|mut err| {
+        err.track_trail_mut().push(point);
+        err
+    })
+}
+
+/// Pushes the current call site onto a [`Trackable`] error's trail, and
+/// returns the `Result` unchanged, so this is typically used right before a
+/// `?`-propagation:
+///
+/// ```
+/// # use tracing_subscriber::track::{self, Trackable, TrackPoint};
+/// # #[derive(Debug)]
+/// # struct MyError(Vec<TrackPoint>);
+/// # impl std::fmt::Display for MyError {
+/// #     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "oh no") }
+/// # }
+/// # impl std::error::Error for MyError {}
+/// # impl Trackable for MyError {
+/// #     fn track_trail_mut(&mut self) -> &mut Vec<TrackPoint> { &mut self.0 }
+/// #     fn track_trail(&self) -> &[TrackPoint] { &self.0 }
+/// # }
+/// fn do_thing() -> Result<(), MyError> {
+///     Err(MyError(Vec::new()))
+/// }
+///
+/// fn do_other_thing() -> Result<(), MyError> {
+///     track!(do_thing())?;
+///     Ok(())
+/// }
+/// ```
+///
+/// The two-argument form, `track!(result, "message")`, additionally attaches
+/// a freeform message (formatted the same way as [`format!`]) to the
+/// recorded [`TrackPoint`].
+#[macro_export]
+macro_rules! track {
+    ($result:expr) => {
+        $crate::track::track(
+            $result,
+            $crate::track::TrackPoint::new(file!(), line!(), module_path!(), None),
+        )
+    };
+    ($result:expr, $($msg:tt)+) => {
+        $crate::track::track(
+            $result,
+            $crate::track::TrackPoint::new(
+                file!(),
+                line!(),
+                module_path!(),
+                Some(format!($($msg)+)),
+            ),
+        )
+    };
+}
+
+/// A [`Subscribe`] that surfaces a [`Trackable`] error's recorded trail as a
+/// structured `track.history` field, whenever a value of that error type is
+/// recorded on an event as a `dyn std::error::Error` --- e.g. via
+/// `tracing::error!(error = &err as &dyn std::error::Error, ...)`. Note that
+/// this has to be the explicit `&dyn Error` cast, not `%err`: `%`/`?`-recorded
+/// values are both routed through [`Visit::record_debug`], not
+/// [`Visit::record_error`], regardless of the value's type.
+///
+/// `TrackableLayer` is generic over the specific error type `E` it looks
+/// for, since recognizing an arbitrary `Trackable` implementor inside
+/// `on_event` requires downcasting a `&(dyn std::error::Error + 'static)` to
+/// a *known* concrete type. Register one `TrackableLayer::<E>::new()` per
+/// tracked error type in use.
+///
+/// Because an [`Event`]'s fields are fixed at the macro call site that
+/// created it, `track.history` can't be appended to the triggering event
+/// itself; instead, `TrackableLayer` emits a second, derived event carrying
+/// just that field, at the same [`Level`](tracing_core::Level) as the
+/// original.
+pub struct TrackableLayer<E> {
+    _error: PhantomData<fn(E)>,
+}
+
+impl<E> TrackableLayer<E> {
+    /// Returns a new `TrackableLayer` for errors of type `E`.
+    pub fn new() -> Self {
+        Self {
+            _error: PhantomData,
+        }
+    }
+}
+
+impl<E> Default for TrackableLayer<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> fmt::Debug for TrackableLayer<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrackableLayer")
+            .field("error", &std::any::type_name::<E>())
+            .finish()
+    }
+}
+
+struct TrailVisitor<'a, E> {
+    history: &'a mut Option<String>,
+    _error: PhantomData<fn(E)>,
+}
+
+impl<E> Visit for TrailVisitor<'_, E>
+where
+    E: Trackable + std::error::Error + 'static,
+{
+    fn record_error(&mut self, _field: &Field, value: &(dyn std::error::Error + 'static)) {
+        if let Some(err) = value.downcast_ref::<E>() {
+            *self.history = Some(format_trail(err.track_trail()));
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {
+        // We only care about values recorded as `dyn Error`; anything else
+        // (including this same error recorded via `Debug`) is ignored.
+    }
+}
+
+/// Formats a trail innermost-to-outermost, one [`TrackPoint`] per line.
+fn format_trail(trail: &[TrackPoint]) -> String {
+    let mut history = String::new();
+    for (i, point) in trail.iter().enumerate() {
+        if i > 0 {
+            history.push('\n');
+        }
+        history.push_str(&point.to_string());
+    }
+    history
+}
+
+impl<C, E> Subscribe<C> for TrackableLayer<E>
+where
+    C: Collect,
+    E: Trackable + std::error::Error + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _cx: Context<'_, C>) {
+        let mut history = None;
+        let mut visitor = TrailVisitor::<E> {
+            history: &mut history,
+            _error: PhantomData,
+        };
+        event.record(&mut visitor);
+
+        if let Some(history) = history {
+            // `Event`'s fields are fixed at the callsite that created it, so
+            // the only way to surface the trail as a *structured* field is
+            // to emit a second, derived event carrying just that field, at
+            // the same level as the original --- a hardcoded level here
+            // (e.g. TRACE) would mean a trail attached to an `error!` event
+            // silently never reaches a subscriber with an ordinary level
+            // filter.
+            //
+            // This pulls in `tracing` (rather than just `tracing-core`,
+            // which the rest of this crate depends on) for the convenience
+            // of the `event!` macro; it's gated behind this module's own
+            // `track` feature so that enabling it is opt-in.
+            match *event.metadata().level() {
+                tracing::Level::ERROR => {
+                    tracing::event!(target: "track", tracing::Level::ERROR, track.history = %history)
+                }
+                tracing::Level::WARN => {
+                    tracing::event!(target: "track", tracing::Level::WARN, track.history = %history)
+                }
+                tracing::Level::INFO => {
+                    tracing::event!(target: "track", tracing::Level::INFO, track.history = %history)
+                }
+                tracing::Level::DEBUG => {
+                    tracing::event!(target: "track", tracing::Level::DEBUG, track.history = %history)
+                }
+                tracing::Level::TRACE => {
+                    tracing::event!(target: "track", tracing::Level::TRACE, track.history = %history)
+                }
+            }
+        }
+    }
+}
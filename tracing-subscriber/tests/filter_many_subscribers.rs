@@ -0,0 +1,113 @@
+#![cfg(feature = "registry")]
+
+//! Regression tests for the per-subscriber filter scalability limit.
+//!
+//! Prior to the growable `FilterMap`/`FilterId` representation, a collector
+//! could host at most 64 `Filtered` subscribers before `FilterId::new`
+//! panicked. These tests register well over 64 and confirm that each one's
+//! enable/disable decision is still tracked independently.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tracing::{collect::with_default, Level};
+use tracing_subscriber::{filter, prelude::*};
+
+const NUM_FILTERS: usize = 100;
+
+/// A no-op subscriber that just counts how many times `on_event` was called
+/// for the given target, so each filtered subscriber can be inspected
+/// independently.
+#[derive(Clone, Default)]
+struct CountingSubscriber {
+    count: Arc<AtomicUsize>,
+}
+
+impl<C> tracing_subscriber::Subscribe<C> for CountingSubscriber
+where
+    C: tracing::Collect + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    fn on_event(&self, _event: &tracing::Event<'_>, _ctx: tracing_subscriber::subscribe::Context<'_, C>) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn more_than_64_filtered_subscribers_stay_independent() {
+    let mut counts = Vec::with_capacity(NUM_FILTERS);
+    let mut subscriber = tracing_subscriber::registry().boxed();
+
+    for i in 0..NUM_FILTERS {
+        let target = format!("target-{}", i);
+        let counting = CountingSubscriber::default();
+        counts.push(counting.count.clone());
+
+        let target_for_filter = target.clone();
+        let filtered = counting.with_filter(filter::dynamic_filter_fn(move |meta, _| {
+            meta.target() == target_for_filter
+        }));
+        subscriber = subscriber.with(filtered).boxed();
+    }
+
+    with_default(subscriber.into(), || {
+        for i in 0..NUM_FILTERS {
+            tracing::event!(target: "target-doesnt-exist", Level::INFO, "nope");
+            let _ = i;
+        }
+
+        for i in 0..NUM_FILTERS {
+            let target: &'static str = Box::leak(format!("target-{}", i).into_boxed_str());
+            tracing::event!(target: target, Level::INFO, "hit");
+        }
+    });
+
+    for (i, count) in counts.into_iter().enumerate() {
+        assert_eq!(
+            count.load(Ordering::SeqCst),
+            1,
+            "subscriber {} should have observed exactly one matching event",
+            i
+        );
+    }
+}
+
+/// Registers filters right at and around the word-boundary counts (63, 64,
+/// 65, 127, 128, 129) to make sure `Bitset`'s spill from an inline `u64` to a
+/// heap-allocated word array doesn't misbehave at the edges.
+#[test]
+fn filtered_subscribers_stay_independent_at_word_boundaries() {
+    for num_filters in [63, 64, 65, 127, 128, 129] {
+        let mut counts = Vec::with_capacity(num_filters);
+        let mut subscriber = tracing_subscriber::registry().boxed();
+
+        for i in 0..num_filters {
+            let target = format!("boundary-{}-{}", num_filters, i);
+            let counting = CountingSubscriber::default();
+            counts.push(counting.count.clone());
+
+            let target_for_filter = target.clone();
+            let filtered = counting.with_filter(filter::dynamic_filter_fn(move |meta, _| {
+                meta.target() == target_for_filter
+            }));
+            subscriber = subscriber.with(filtered).boxed();
+        }
+
+        with_default(subscriber.into(), || {
+            for i in 0..num_filters {
+                let target: &'static str =
+                    Box::leak(format!("boundary-{}-{}", num_filters, i).into_boxed_str());
+                tracing::event!(target: target, Level::INFO, "hit");
+            }
+        });
+
+        for (i, count) in counts.into_iter().enumerate() {
+            assert_eq!(
+                count.load(Ordering::SeqCst),
+                1,
+                "with {} filters registered, subscriber {} should have observed exactly one event",
+                num_filters,
+                i
+            );
+        }
+    }
+}
@@ -0,0 +1,175 @@
+#![cfg(feature = "registry")]
+
+//! Regression tests for `FilterExt::not_strict`.
+//!
+//! The plain `FilterExt::not` combinator deliberately doesn't invert
+//! `event_enabled`, since doing so correctly requires remembering the
+//! wrapped filter's `enabled` result. These tests cover `not_strict`, which
+//! pays for that bookkeeping in order to invert field-value filtering
+//! exactly.
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tracing::{
+    collect::with_default,
+    field::{Field, Visit},
+    Event, Level, Metadata,
+};
+use tracing_subscriber::{
+    filter::FilterExt,
+    prelude::*,
+    subscribe::{Context, Filter},
+};
+
+/// A filter that enables everything via `enabled`, but rejects events whose
+/// `reject` field is `true` via `event_enabled`.
+struct RejectFieldFilter;
+
+impl<S> Filter<S> for RejectFieldFilter {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        struct RejectVisitor(bool);
+        impl Visit for RejectVisitor {
+            fn record_bool(&mut self, field: &Field, value: bool) {
+                if field.name() == "reject" {
+                    self.0 = value;
+                }
+            }
+            fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+        }
+
+        let mut visitor = RejectVisitor(false);
+        event.record(&mut visitor);
+        !visitor.0
+    }
+}
+
+#[derive(Clone, Default)]
+struct CountingSubscriber {
+    count: Arc<AtomicUsize>,
+}
+
+impl<C> tracing_subscriber::Subscribe<C> for CountingSubscriber
+where
+    C: tracing::Collect + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    fn on_event(&self, _event: &Event<'_>, _ctx: tracing_subscriber::subscribe::Context<'_, C>) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn not_strict_inverts_event_enabled() {
+    let counting = CountingSubscriber::default();
+    let count = counting.count.clone();
+
+    let subscriber =
+        tracing_subscriber::registry().with(counting.with_filter(RejectFieldFilter.not_strict()));
+
+    with_default(subscriber, || {
+        // `enabled` allows this, but `event_enabled` would reject it
+        // (`reject: true`). Inverted by `not_strict`, it should be recorded.
+        tracing::event!(Level::INFO, reject = true, "should be recorded");
+
+        // `enabled` allows this, and `event_enabled` would also allow it
+        // (`reject: false`). Inverted by `not_strict`, it should be dropped.
+        tracing::event!(Level::INFO, reject = false, "should be dropped");
+    });
+
+    assert_eq!(
+        count.load(Ordering::SeqCst),
+        1,
+        "only the event rejected by the wrapped filter's `event_enabled` should be recorded"
+    );
+}
+
+/// Always rejects via `enabled` (regardless of metadata), and rejects events
+/// whose `reject_b` field is `true` via `event_enabled` --- standing in for a
+/// filter (e.g. a field-value or level filter) whose `enabled` result is
+/// meaningfully `false`, rather than the trivially-`true` `enabled` of
+/// [`RejectFieldFilter`] above.
+struct AlwaysDisabledFilter;
+
+impl<S> Filter<S> for AlwaysDisabledFilter {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        false
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        struct RejectVisitor(bool);
+        impl Visit for RejectVisitor {
+            fn record_bool(&mut self, field: &Field, value: bool) {
+                if field.name() == "reject_b" {
+                    self.0 = value;
+                }
+            }
+            fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+        }
+
+        let mut visitor = RejectVisitor(false);
+        event.record(&mut visitor);
+        !visitor.0
+    }
+}
+
+#[test]
+fn composed_not_strict_filters_do_not_clobber_each_others_stash() {
+    // Two `not_strict` filters, composed under the same `Filtered` via
+    // `and`. `RejectFieldFilter`'s wrapped `enabled()` is always `true`, but
+    // `AlwaysDisabledFilter`'s is always `false` --- if the two `Not`
+    // instances shared a single stash slot keyed by the outer `Filtered`'s
+    // `FilterId` (rather than each having its own), the first `Not`'s
+    // `event_enabled` call would consume the slot before the second one
+    // could read its own stashed `false`, and the second would silently fall
+    // back to `unwrap_or(true)`.
+    let counting = CountingSubscriber::default();
+    let count = counting.count.clone();
+
+    let filter = RejectFieldFilter.not_strict().and(AlwaysDisabledFilter.not_strict());
+    let subscriber = tracing_subscriber::registry().with(counting.with_filter(filter));
+
+    with_default(subscriber, || {
+        // `reject: true` on `RejectFieldFilter`'s field makes its `Not`'s
+        // `event_enabled` pass regardless of how `AlwaysDisabledFilter`'s
+        // stash is (mis)handled; what this checks is `AlwaysDisabledFilter`'s
+        // own `Not`, whose correctly-stashed `enabled() == false` should
+        // make it report `true` no matter what its `reject_b` field is. Had
+        // it fallen back to `true`, the `reject_b: false` event would be
+        // dropped.
+        tracing::event!(Level::INFO, reject = true, reject_b = true, "reject_b true");
+        tracing::event!(Level::INFO, reject = true, reject_b = false, "reject_b false");
+    });
+
+    assert_eq!(
+        count.load(Ordering::SeqCst),
+        2,
+        "each `not_strict` filter's stashed `enabled` result must be kept separate, \
+         not clobbered by the other `Not` instance composed alongside it"
+    );
+}
+
+#[test]
+fn not_non_strict_does_not_invert_event_enabled() {
+    let counting = CountingSubscriber::default();
+    let count = counting.count.clone();
+
+    let subscriber =
+        tracing_subscriber::registry().with(counting.with_filter(RejectFieldFilter.not()));
+
+    with_default(subscriber, || {
+        tracing::event!(Level::INFO, reject = true, "not inverted");
+        tracing::event!(Level::INFO, reject = false, "not inverted");
+    });
+
+    assert_eq!(
+        count.load(Ordering::SeqCst),
+        0,
+        "plain `not` should leave `event_enabled` un-inverted, so `enabled() == true` \
+         short-circuits both events to disabled"
+    );
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "registry")]
+
+//! Tests for the public `FilterOutcome` introspection API.
+
+use std::sync::{Arc, Mutex};
+
+use tracing::{collect::with_default, Level, Metadata};
+use tracing_subscriber::{
+    filter::{self, FilterIdAllocator, FilterOutcome},
+    prelude::*,
+    subscribe::Context,
+};
+
+const NUM_FILTERS: usize = 2;
+
+/// A subscriber with no filter of its own that, for every span/event,
+/// records how many of the `NUM_FILTERS` per-subscriber filters registered
+/// ahead of it in the stack disabled it, and whether the first of them
+/// (`FilterId` 0) specifically did.
+#[derive(Clone, Default)]
+struct IntrospectingSubscriber {
+    observed: Arc<Mutex<Vec<(usize, bool)>>>,
+}
+
+impl<C> tracing_subscriber::Subscribe<C> for IntrospectingSubscriber
+where
+    C: tracing::Collect + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    fn enabled(&self, _meta: &Metadata<'_>, _ctx: Context<'_, C>) -> bool {
+        true
+    }
+
+    fn event_enabled(&self, _event: &tracing::Event<'_>, _ctx: Context<'_, C>) -> bool {
+        let outcome = FilterOutcome::current();
+        let disabled_count = outcome.disabled_by(NUM_FILTERS).count();
+
+        // Mirror the `FilterId`s the registry hands out to the first two
+        // `Filtered` subscribers added below, to exercise `is_enabled_by`
+        // directly rather than only the `disabled_by` count.
+        let allocator = FilterIdAllocator::new();
+        let first_filter = allocator.next();
+        let first_enabled = outcome.is_enabled_by(&first_filter);
+
+        self.observed
+            .lock()
+            .unwrap()
+            .push((disabled_count, first_enabled));
+        true
+    }
+}
+
+#[test]
+fn filter_outcome_reports_disabling_filters() {
+    let introspecting = IntrospectingSubscriber::default();
+    let observed = introspecting.observed.clone();
+
+    let accepts_a = filter::dynamic_filter_fn(|meta, _| meta.target() == "target-a");
+    let accepts_b = filter::dynamic_filter_fn(|meta, _| meta.target() == "target-b");
+
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::subscriber().with_filter(accepts_a))
+        .with(tracing_subscriber::fmt::subscriber().with_filter(accepts_b))
+        .with(introspecting);
+
+    with_default(subscriber, || {
+        // Only the first filter (accepts "target-a") lets this through, so
+        // exactly one of the two filters disabled it.
+        tracing::event!(target: "target-a", Level::INFO, "a");
+        // Only the second filter lets this through.
+        tracing::event!(target: "target-b", Level::INFO, "b");
+        // Neither filter lets this through.
+        tracing::event!(target: "target-neither", Level::INFO, "neither");
+    });
+
+    let observed = observed.lock().unwrap();
+    assert_eq!(
+        *observed,
+        vec![(1, true), (1, false), (2, false)],
+        "FilterOutcome should report exactly which registered filters disabled each event"
+    );
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "registry")]
+
+//! Tests for `FilterExt::sample_rate`, confirming it thins events down to
+//! roughly the configured fraction rather than consuming the per-callsite
+//! counter twice per event (once in `enabled`, again in `event_enabled`) and
+//! dropping nearly everything.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tracing::{collect::with_default, Level};
+use tracing_subscriber::{filter, prelude::*};
+
+/// A no-op subscriber that just counts how many times `on_event` was called.
+#[derive(Clone, Default)]
+struct CountingSubscriber {
+    count: Arc<AtomicUsize>,
+}
+
+impl<C> tracing_subscriber::Subscribe<C> for CountingSubscriber
+where
+    C: tracing::Collect + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    fn on_event(&self, _event: &tracing::Event<'_>, _ctx: tracing_subscriber::subscribe::Context<'_, C>) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn every_nth_lets_through_exactly_one_in_n() {
+    const N: u64 = 10;
+    const CALLS: usize = 100;
+
+    let counting = CountingSubscriber::default();
+    let count = counting.count.clone();
+
+    let filter = filter::dynamic_filter_fn(|meta, _| meta.target() == "sampled")
+        .sample_rate(N);
+    let subscriber = tracing_subscriber::registry().with(counting.with_filter(filter));
+
+    with_default(subscriber, || {
+        for _ in 0..CALLS {
+            tracing::event!(target: "sampled", Level::INFO, "hit");
+        }
+    });
+
+    assert_eq!(
+        count.load(Ordering::SeqCst),
+        CALLS / N as usize,
+        "every_nth({}) should let through exactly 1 in every {} calls at this callsite",
+        N,
+        N
+    );
+}
+
+#[test]
+fn per_second_burst_lets_through_roughly_the_configured_fraction() {
+    const BURST: f64 = 50.0;
+    const CALLS: usize = 1000;
+
+    // A tight loop burns through this burst allowance near-instantly, well
+    // before the token bucket meaningfully refills (`events_per_sec` is set
+    // low relative to how fast the loop runs), so the count should land at
+    // roughly `BURST` rather than being driven to ~0 by double-consuming the
+    // bucket once per `enabled`/`event_enabled` pair.
+    let counting = CountingSubscriber::default();
+    let count = counting.count.clone();
+
+    let filter =
+        filter::dynamic_filter_fn(|meta, _| meta.target() == "tight-loop").sample_rate_per_second(1.0, BURST);
+    let subscriber = tracing_subscriber::registry().with(counting.with_filter(filter));
+
+    with_default(subscriber, || {
+        for _ in 0..CALLS {
+            tracing::event!(target: "tight-loop", Level::DEBUG, "hit");
+        }
+    });
+
+    let observed = count.load(Ordering::SeqCst) as f64;
+    assert!(
+        (BURST - 1.0..=BURST + 1.0).contains(&observed),
+        "expected roughly {} events to get through the burst allowance, got {}",
+        BURST,
+        observed
+    );
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "registry")]
+
+//! Regression test for `FilterExt::when`'s `event_enabled`.
+//!
+//! `When::event_enabled` must reuse whatever `enabled` already decided about
+//! whether its `predicate` applies, rather than asking
+//! `predicate.event_enabled` fresh --- the two can disagree (e.g. for a
+//! plain `filter_fn` predicate, whose `event_enabled` isn't overridden and
+//! so defaults to `true` regardless of what `enabled` returned).
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tracing::{
+    collect::with_default,
+    field::{Field, Visit},
+    span, Event, Level, Metadata,
+};
+use tracing_subscriber::{
+    filter::FilterExt,
+    prelude::*,
+    subscribe::{Context, Filter},
+};
+
+/// Only applies to events targeting `"interesting"`, via `enabled`. Its
+/// `event_enabled` is left un-overridden in spirit --- it always returns
+/// `true`, standing in for a plain `filter_fn` predicate, whose
+/// `event_enabled` defaults to `true` regardless of what `enabled` decided.
+struct TargetPredicate;
+
+impl<S> Filter<S> for TargetPredicate {
+    fn enabled(&self, meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        meta.target() == "interesting"
+    }
+
+    fn event_enabled(&self, _event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+}
+
+/// Rejects events whose `reject` field is `true`.
+struct RejectFieldFilter;
+
+impl<S> Filter<S> for RejectFieldFilter {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        struct RejectVisitor(bool);
+        impl Visit for RejectVisitor {
+            fn record_bool(&mut self, field: &Field, value: bool) {
+                if field.name() == "reject" {
+                    self.0 = value;
+                }
+            }
+            fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+        }
+
+        let mut visitor = RejectVisitor(false);
+        event.record(&mut visitor);
+        !visitor.0
+    }
+}
+
+/// A predicate that counts `on_new_span` calls it receives, to confirm
+/// `When` forwards span lifecycle hooks to `predicate` as well as `then`,
+/// the same way `And`/`Or`/`Xor` forward them to both sides.
+#[derive(Clone, Default)]
+struct SpanCountingPredicate {
+    new_spans: Arc<AtomicUsize>,
+}
+
+impl<S> Filter<S> for SpanCountingPredicate {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, _id: &span::Id, _cx: Context<'_, S>) {
+        self.new_spans.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Clone, Default)]
+struct CountingSubscriber {
+    count: Arc<AtomicUsize>,
+}
+
+impl<C> tracing_subscriber::Subscribe<C> for CountingSubscriber
+where
+    C: tracing::Collect + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    fn on_event(&self, _event: &Event<'_>, _ctx: tracing_subscriber::subscribe::Context<'_, C>) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn when_event_enabled_reuses_enabled_decision_not_predicate_event_enabled() {
+    let counting = CountingSubscriber::default();
+    let count = counting.count.clone();
+
+    let filter = RejectFieldFilter.when(TargetPredicate);
+    let subscriber = tracing_subscriber::registry().with(counting.with_filter(filter));
+
+    with_default(subscriber, || {
+        // The predicate's `enabled` matches this event's target, so `then`
+        // should apply, and reject it (`reject: true`).
+        tracing::event!(target: "interesting", Level::INFO, reject = true, "should be dropped");
+
+        // The predicate's `enabled` does *not* match this event's target, so
+        // `then` should not apply at all --- it should fall through to
+        // enabled, regardless of `reject`, even though `TargetPredicate`'s
+        // own `event_enabled` (which ignores the target) would say it does.
+        tracing::event!(target: "boring", Level::INFO, reject = true, "should be recorded");
+    });
+
+    assert_eq!(
+        count.load(Ordering::SeqCst),
+        1,
+        "`then` must only run for events the predicate's `enabled` actually matched"
+    );
+}
+
+#[test]
+fn when_forwards_span_lifecycle_hooks_to_predicate() {
+    let predicate = SpanCountingPredicate::default();
+    let new_spans = predicate.new_spans.clone();
+
+    let filter = RejectFieldFilter.when(predicate);
+    let subscriber =
+        tracing_subscriber::registry().with(CountingSubscriber::default().with_filter(filter));
+
+    with_default(subscriber, || {
+        let span = tracing::info_span!("a span");
+        let _entered = span.enter();
+    });
+
+    assert_eq!(
+        new_spans.load(Ordering::SeqCst),
+        1,
+        "`When` must forward on_new_span to `predicate`, not just `then`, \
+         the same way And/Or/Xor forward every hook to both sides"
+    );
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "registry")]
+
+//! Regression test for `FilterExt::xor`.
+//!
+//! The request that introduced `xor` called out its `Interest` merging as
+//! the trickiest part of the combinator, since --- unlike `And`/`Or`, which
+//! can short-circuit once either side settles the answer --- an
+//! exclusive-or's result always depends on *both* sides, so it can only
+//! settle on a static `Interest` when both sides are themselves static.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tracing::{collect::with_default, Event, Metadata};
+use tracing_core::collect::Interest;
+use tracing_subscriber::{
+    filter::FilterExt,
+    prelude::*,
+    subscribe::{Context, Filter},
+};
+
+/// A `Filter` whose `enabled`/`event_enabled`/`callsite_enabled` are all
+/// fixed at construction, so `Xor`'s merging logic can be pinned down
+/// independent of any real filtering decision.
+struct Fixed {
+    enabled: bool,
+    interest: Interest,
+}
+
+impl Fixed {
+    fn new(enabled: bool, interest: Interest) -> Self {
+        Self { enabled, interest }
+    }
+}
+
+impl<S> Filter<S> for Fixed {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        self.enabled
+    }
+
+    fn event_enabled(&self, _event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        self.enabled
+    }
+
+    fn callsite_enabled(&self, _meta: &'static Metadata<'static>) -> Interest {
+        self.interest.clone()
+    }
+}
+
+#[derive(Clone, Default)]
+struct CountingSubscriber {
+    count: Arc<AtomicUsize>,
+}
+
+impl<C> tracing_subscriber::Subscribe<C> for CountingSubscriber
+where
+    C: tracing::Collect + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    fn on_event(&self, _event: &Event<'_>, _cx: Context<'_, C>) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Captures the first event's `'static` metadata it sees, so it can be
+/// reused afterwards to call `callsite_enabled` directly.
+#[derive(Clone, Default)]
+struct MetadataCapture {
+    metadata: Arc<Mutex<Option<&'static Metadata<'static>>>>,
+}
+
+impl<C> tracing_subscriber::Subscribe<C> for MetadataCapture
+where
+    C: tracing::Collect + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    fn on_event(&self, event: &Event<'_>, _cx: Context<'_, C>) {
+        *self.metadata.lock().unwrap() = Some(event.metadata());
+    }
+}
+
+fn counts_events(filter: impl Filter<tracing_subscriber::registry::Registry> + Send + Sync + 'static) -> usize {
+    let counting = CountingSubscriber::default();
+    let count = counting.count.clone();
+    let subscriber = tracing_subscriber::registry().with(counting.with_filter(filter));
+    with_default(subscriber, || {
+        tracing::info!("does this get through?");
+    });
+    count.load(Ordering::SeqCst)
+}
+
+#[test]
+fn xor_disables_when_both_sides_agree() {
+    assert_eq!(
+        counts_events(Fixed::new(true, Interest::always()).xor(Fixed::new(true, Interest::always()))),
+        0,
+        "both sides enabling should cancel out under xor"
+    );
+    assert_eq!(
+        counts_events(Fixed::new(false, Interest::never()).xor(Fixed::new(false, Interest::never()))),
+        0,
+        "both sides disabling should also cancel out under xor"
+    );
+}
+
+#[test]
+fn xor_enables_when_sides_disagree() {
+    assert_eq!(
+        counts_events(Fixed::new(true, Interest::always()).xor(Fixed::new(false, Interest::never()))),
+        1,
+        "one side enabling and the other disabling should enable under xor"
+    );
+    assert_eq!(
+        counts_events(Fixed::new(false, Interest::never()).xor(Fixed::new(true, Interest::always()))),
+        1,
+        "xor should be symmetric regardless of which side enables"
+    );
+}
+
+/// Gets a real `&'static Metadata<'static>` to drive `callsite_enabled`
+/// tests against, by capturing one off a real event.
+fn any_metadata() -> &'static Metadata<'static> {
+    let capture = MetadataCapture::default();
+    let metadata = capture.metadata.clone();
+    let subscriber = tracing_subscriber::registry().with(capture);
+    with_default(subscriber, || {
+        tracing::info!("just here to produce some metadata");
+    });
+    metadata.lock().unwrap().take().unwrap()
+}
+
+#[test]
+fn xor_callsite_enabled_settles_only_when_both_sides_are_static() {
+    let meta = any_metadata();
+
+    // Both sides statically `always` --- result is static (and always
+    // disabled, since true ^ true is false).
+    let both_always = Fixed::new(true, Interest::always()).xor(Fixed::new(true, Interest::always()));
+    assert!(Filter::<tracing_subscriber::registry::Registry>::callsite_enabled(&both_always, meta).is_never());
+
+    // One side `always`, the other `never` --- result is static (and always
+    // enabled, since true ^ false is true).
+    let disagreeing_static =
+        Fixed::new(true, Interest::always()).xor(Fixed::new(false, Interest::never()));
+    assert!(
+        Filter::<tracing_subscriber::registry::Registry>::callsite_enabled(&disagreeing_static, meta).is_always()
+    );
+
+    // Either side `sometimes` --- the outcome can only be known once the
+    // actual span/event is seen, so the combined `Interest` can't be static.
+    let one_side_dynamic =
+        Fixed::new(true, Interest::always()).xor(Fixed::new(false, Interest::sometimes()));
+    assert!(
+        Filter::<tracing_subscriber::registry::Registry>::callsite_enabled(&one_side_dynamic, meta).is_sometimes()
+    );
+}
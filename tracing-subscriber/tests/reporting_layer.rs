@@ -0,0 +1,337 @@
+#![cfg(feature = "registry")]
+
+//! Tests for `ReportingLayer`'s level threshold, sampling, and breadcrumb
+//! behavior, exercised against an in-memory `ReportTransport` fake.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tracing::collect::with_default;
+use tracing_subscriber::{
+    filter::LevelFilter,
+    prelude::*,
+    report::{Report, ReportTransport, ReportingLayer},
+};
+
+/// An in-memory `ReportTransport` that just stashes every batch it's handed,
+/// so tests can assert on what would have been sent.
+#[derive(Clone, Default)]
+struct CollectingTransport {
+    batches: Arc<Mutex<Vec<Vec<Report>>>>,
+}
+
+impl ReportTransport for CollectingTransport {
+    fn send_batch(&self, reports: Vec<Report>) {
+        self.batches.lock().unwrap().push(reports);
+    }
+}
+
+impl CollectingTransport {
+    /// Flattens every batch received so far into a single list of reports.
+    fn reports(&self) -> Vec<Report> {
+        self.batches
+            .lock()
+            .unwrap()
+            .iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// The number of distinct `send_batch` calls made so far.
+    fn batch_count(&self) -> usize {
+        self.batches.lock().unwrap().len()
+    }
+}
+
+/// Waits (briefly) for the background batcher thread to have flushed
+/// whatever's been sent so far, since `ReportingLayer` hands reports off
+/// rather than sending them synchronously.
+fn wait_for_batcher() {
+    std::thread::sleep(Duration::from_millis(50));
+}
+
+#[test]
+fn only_events_at_or_above_min_level_are_reported() {
+    let transport = CollectingTransport::default();
+    let layer = ReportingLayer::new(
+        transport.clone(),
+        "test-release",
+        LevelFilter::WARN,
+        1.0,
+        Duration::from_millis(10),
+    );
+
+    let subscriber = tracing_subscriber::registry().with(layer);
+    with_default(subscriber, || {
+        tracing::info!("below threshold, breadcrumb only");
+        tracing::warn!("at threshold, reported");
+        tracing::error!("above threshold, reported");
+    });
+
+    wait_for_batcher();
+    let reports = transport.reports();
+    assert_eq!(reports.len(), 2, "only WARN and ERROR should be reported");
+    assert_eq!(reports[0].level, "WARN");
+    assert_eq!(reports[1].level, "ERROR");
+}
+
+#[test]
+fn zero_sample_rate_reports_nothing() {
+    let transport = CollectingTransport::default();
+    let layer = ReportingLayer::new(
+        transport.clone(),
+        "test-release",
+        LevelFilter::WARN,
+        0.0,
+        Duration::from_millis(10),
+    );
+
+    let subscriber = tracing_subscriber::registry().with(layer);
+    with_default(subscriber, || {
+        tracing::error!("should be sampled out entirely");
+    });
+
+    wait_for_batcher();
+    assert!(
+        transport.reports().is_empty(),
+        "a sample rate of 0.0 should never report"
+    );
+}
+
+#[test]
+fn reported_event_includes_preceding_breadcrumbs() {
+    let transport = CollectingTransport::default();
+    let layer = ReportingLayer::new(
+        transport.clone(),
+        "test-release",
+        LevelFilter::ERROR,
+        1.0,
+        Duration::from_millis(10),
+    );
+
+    let subscriber = tracing_subscriber::registry().with(layer);
+    with_default(subscriber, || {
+        tracing::info!(step = "first", "doing a thing");
+        tracing::info!(step = "second", "doing another thing");
+        tracing::error!("it broke");
+    });
+
+    wait_for_batcher();
+    let reports = transport.reports();
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert_eq!(report.release, "test-release");
+    assert_eq!(report.breadcrumbs.len(), 2);
+    assert_eq!(
+        report.breadcrumbs[0].fields.get("step").and_then(|v| v.as_str()),
+        Some("first")
+    );
+    assert_eq!(
+        report.breadcrumbs[1].fields.get("step").and_then(|v| v.as_str()),
+        Some("second")
+    );
+}
+
+#[test]
+fn triggering_event_is_not_its_own_breadcrumb() {
+    let transport = CollectingTransport::default();
+    let layer = ReportingLayer::new(
+        transport.clone(),
+        "test-release",
+        LevelFilter::ERROR,
+        1.0,
+        Duration::from_millis(10),
+    );
+
+    let subscriber = tracing_subscriber::registry().with(layer);
+    with_default(subscriber, || {
+        tracing::error!("it broke");
+    });
+
+    wait_for_batcher();
+    let reports = transport.reports();
+    assert_eq!(reports.len(), 1);
+    assert!(
+        reports[0].breadcrumbs.is_empty(),
+        "a triggering event with no preceding events should not appear as its own breadcrumb"
+    );
+}
+
+#[test]
+fn breadcrumb_ring_is_bounded() {
+    let transport = CollectingTransport::default();
+    let layer = ReportingLayer::new(
+        transport.clone(),
+        "test-release",
+        LevelFilter::ERROR,
+        1.0,
+        Duration::from_millis(10),
+    )
+    .with_breadcrumb_capacity(2);
+
+    let subscriber = tracing_subscriber::registry().with(layer);
+    with_default(subscriber, || {
+        for i in 0..5 {
+            tracing::info!(i, "filler breadcrumb");
+        }
+        tracing::error!("it broke");
+    });
+
+    wait_for_batcher();
+    let reports = transport.reports();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(
+        reports[0].breadcrumbs.len(),
+        2,
+        "the breadcrumb ring should be capped at its configured capacity"
+    );
+}
+
+#[test]
+fn reports_fired_in_quick_succession_land_in_one_batch() {
+    let transport = CollectingTransport::default();
+    let layer = ReportingLayer::new(
+        transport.clone(),
+        "test-release",
+        LevelFilter::ERROR,
+        1.0,
+        Duration::from_millis(100),
+    );
+
+    let subscriber = tracing_subscriber::registry().with(layer);
+    with_default(subscriber, || {
+        for _ in 0..5 {
+            tracing::error!("part of the same burst");
+        }
+    });
+
+    // This batch_interval is longer than the other tests' so the whole burst
+    // lands inside one debounce window; sleep well past it before checking.
+    std::thread::sleep(Duration::from_millis(200));
+    assert_eq!(transport.reports().len(), 5, "all 5 reports should arrive");
+    assert_eq!(
+        transport.batch_count(),
+        1,
+        "a burst of reports within batch_interval should debounce into a single batch, \
+         not be flushed as soon as the channel first runs dry"
+    );
+}
+
+#[test]
+fn report_spans_are_ordered_root_to_leaf() {
+    let transport = CollectingTransport::default();
+    let layer = ReportingLayer::new(
+        transport.clone(),
+        "test-release",
+        LevelFilter::ERROR,
+        1.0,
+        Duration::from_millis(10),
+    );
+
+    let subscriber = tracing_subscriber::registry().with(layer);
+    with_default(subscriber, || {
+        let root = tracing::info_span!("root");
+        let _root = root.enter();
+        let child = tracing::info_span!("child");
+        let _child = child.enter();
+        let leaf = tracing::info_span!("leaf");
+        let _leaf = leaf.enter();
+
+        tracing::error!("it broke");
+    });
+
+    wait_for_batcher();
+    let reports = transport.reports();
+    assert_eq!(reports.len(), 1);
+    let names: Vec<&str> = reports[0].spans.iter().map(|s| s.name).collect();
+    assert_eq!(
+        names,
+        vec!["root", "child", "leaf"],
+        "a report's spans should be ordered from root to leaf, as documented"
+    );
+}
+
+#[test]
+fn stacked_reporting_layers_keep_separate_breadcrumb_trails() {
+    // Two `ReportingLayer`s on the same `Registry`, each with its own
+    // capacity, is a normal use of `Subscribe` composability (e.g. one
+    // reporting to an internal sink, another to an external service at a
+    // higher threshold) --- their breadcrumb trails and capacities must not
+    // leak into each other.
+    let inner_transport = CollectingTransport::default();
+    let inner = ReportingLayer::new(
+        inner_transport.clone(),
+        "test-release",
+        LevelFilter::WARN,
+        1.0,
+        Duration::from_millis(10),
+    )
+    .with_breadcrumb_capacity(1);
+
+    let outer_transport = CollectingTransport::default();
+    let outer = ReportingLayer::new(
+        outer_transport.clone(),
+        "test-release",
+        LevelFilter::ERROR,
+        1.0,
+        Duration::from_millis(10),
+    )
+    .with_breadcrumb_capacity(5);
+
+    let subscriber = tracing_subscriber::registry().with(inner).with(outer);
+    with_default(subscriber, || {
+        tracing::info!("doing a thing");
+        tracing::warn!("getting worse");
+        tracing::error!("it broke");
+    });
+
+    wait_for_batcher();
+
+    let inner_reports = inner_transport.reports();
+    assert_eq!(inner_reports.len(), 2, "inner layer reports at WARN and ERROR");
+    assert_eq!(
+        inner_reports[0].breadcrumbs.len(),
+        1,
+        "inner layer's own ring, capped at 1, should not be shared with the outer layer"
+    );
+    assert_eq!(
+        inner_reports[1].breadcrumbs.len(),
+        1,
+        "inner layer's capacity of 1 should stay capped at 1, not the outer layer's 5"
+    );
+
+    let outer_reports = outer_transport.reports();
+    assert_eq!(outer_reports.len(), 1, "outer layer only reports at ERROR");
+    assert_eq!(
+        outer_reports[0].breadcrumbs.len(),
+        2,
+        "outer layer's own breadcrumb trail should hold both preceding events, undoubled"
+    );
+}
+
+#[test]
+fn full_sample_rate_always_reports() {
+    let transport = CollectingTransport::default();
+    let layer = ReportingLayer::new(
+        transport.clone(),
+        "test-release",
+        LevelFilter::ERROR,
+        1.0,
+        Duration::from_millis(10),
+    );
+
+    let subscriber = tracing_subscriber::registry().with(layer);
+    with_default(subscriber, || {
+        for _ in 0..10 {
+            tracing::error!("always reported");
+        }
+    });
+
+    wait_for_batcher();
+    assert_eq!(
+        transport.reports().len(),
+        10,
+        "a sample rate of 1.0 should report every triggering event"
+    );
+}
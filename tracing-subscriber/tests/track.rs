@@ -0,0 +1,126 @@
+#![cfg(feature = "track")]
+
+//! Integration test for [`track!`]/[`TrackableLayer`]: propagates an error
+//! through a couple of `track!` call sites and confirms the derived
+//! `target: "track"` event carries the accumulated trail as a
+//! `track.history` field.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use tracing::{
+    collect::with_default,
+    field::{Field, Visit},
+    Event,
+};
+use tracing_subscriber::{
+    prelude::*,
+    subscribe::Context,
+    track::{self, Trackable, TrackPoint, TrackableLayer},
+};
+
+#[derive(Debug)]
+struct MyError {
+    message: &'static str,
+    trail: Vec<TrackPoint>,
+}
+
+impl fmt::Display for MyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for MyError {}
+
+impl Trackable for MyError {
+    fn track_trail_mut(&mut self) -> &mut Vec<TrackPoint> {
+        &mut self.trail
+    }
+
+    fn track_trail(&self) -> &[TrackPoint] {
+        &self.trail
+    }
+}
+
+fn innermost() -> Result<(), MyError> {
+    Err(MyError {
+        message: "oh no",
+        trail: Vec::new(),
+    })
+}
+
+fn middle() -> Result<(), MyError> {
+    track::track(
+        innermost(),
+        TrackPoint::new(file!(), line!(), module_path!(), None),
+    )?;
+    Ok(())
+}
+
+fn outer() -> Result<(), MyError> {
+    track!(middle(), "propagated from outer")?;
+    Ok(())
+}
+
+/// Captures the `track.history` field of the first event targeting
+/// `"track"` it sees.
+#[derive(Clone, Default)]
+struct HistoryCapture {
+    history: Arc<Mutex<Option<String>>>,
+}
+
+impl<C> tracing_subscriber::Subscribe<C> for HistoryCapture
+where
+    C: tracing::Collect,
+{
+    fn on_event(&self, event: &Event<'_>, _cx: Context<'_, C>) {
+        if event.metadata().target() != "track" {
+            return;
+        }
+
+        struct HistoryVisitor<'a>(&'a mut Option<String>);
+        impl Visit for HistoryVisitor<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "track.history" {
+                    *self.0 = Some(format!("{:?}", value));
+                }
+            }
+        }
+
+        let mut history = self.history.lock().unwrap();
+        event.record(&mut HistoryVisitor(&mut history));
+    }
+}
+
+#[test]
+fn tracked_error_trail_is_surfaced_as_track_history() {
+    let capture = HistoryCapture::default();
+    let history = capture.history.clone();
+
+    let subscriber = tracing_subscriber::registry()
+        .with(TrackableLayer::<MyError>::new())
+        .with(capture);
+
+    with_default(subscriber, || {
+        let err = outer().unwrap_err();
+        tracing::error!(error = &err as &dyn std::error::Error, "it broke");
+    });
+
+    let history = history.lock().unwrap();
+    let history = history
+        .as_ref()
+        .expect("TrackableLayer should have recorded a track.history field");
+
+    assert_eq!(
+        history.lines().count(),
+        2,
+        "trail should carry one track point per track!/track() call: {}",
+        history
+    );
+    assert!(
+        history.contains("propagated from outer"),
+        "the outermost track!'s message should appear in the trail: {}",
+        history
+    );
+}